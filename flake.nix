@@ -46,8 +46,207 @@
           flakelock ? null
           # how old the flake can be before it is out of date
           , threshold ? 14
+          # windows with their own thresholds, the first matching one wins, e.g.
+          # [{ days = [ "Sat" "Sun" ]; from = 0; to = 24; threshold = 7; }]
+          # good and update default to the usual 3 and 4
+          , schedule ? [ ]
+          # other flakes to show the age of in the same block, each with its own thresholds, e.g.
+          # [{ name = "dotfiles"; flakelock = ./dotfiles/flake.lock; threshold = 30; }]
+          # the block takes the state of whichever is worst
+          , trackedflakes ? [ ]
           # the i3status icon the bar will be displayed with
           , icon ? "cogs"
+          # the timezone the lock date, schedule and calendar days go by: "local" (the system's, or
+          # TZ), "UTC" or a fixed offset like "+05:30"
+          , timezone ? "local"
+          # count the age in midnights passed in that timezone instead of whole 24 hour periods
+          , calendardays ? false
+          # an ICS file of planned maintenance windows, read at runtime (fetch a remote calendar
+          # into it from a timer): during a window the age never gets past Info, and once a window
+          # has passed without an update it's Critical straight away
+          , maintenancecalendar ? null
+          # for how many days after the machine was installed (going by /etc/machine-id) the age never
+          # gets past Info, so a machine provisioned from an older installer image doesn't come up red
+          , installgracedays ? null
+          # for hosts deployed from elsewhere: the tool doing it, e.g. "colmena", "deploy-rs" or
+          # "morph", so an out of date lock reads "awaiting colmena deploy" rather than asking for an
+          # update, and/or a file the deploy touches, e.g. from an activation script, whose age is
+          # shown as "Deployed: N" (and which also marks the host as deployed when it exists)
+          , deployedby ? null
+          , deploymarker ? null
+          # for how many days after an update to mention which inputs it moved and how far, in
+          # --details and the waybar tooltip, null to not keep track
+          , showupdatedays ? null
+          # what to show for the lock: any of "age" (days since it was updated), "date" ("locked
+          # 2024-05-02"), "week" ("locked 2024-W18") and "countdown" ("update within 3d"), in order
+          , agedisplay ? [ "age" ]
+          # show the age as a phrase like "vor 3 Tagen" instead of "Age: 3", in the given language
+          # ("en", "de", "fr", "es", "nl"), or "auto" to go by LC_ALL/LC_MESSAGES/LANG
+          , agelanguage ? null
+          # whether to point out a lockfile dated in the future
+          , clockskewhint ? true
+          # whether to also show days since boot
+          , uptime ? false
+          # whether to also show how many days old the running kernel is: its build date, or for
+          # reproducible builds like nixpkgs' when it arrived in the store (which needs nix to run)
+          , kernelage ? false
+          # whether to check the bootloader defaults to the latest generation and its boot files match
+          # (systemd-boot, GRUB or extlinux; a glob or saved default can't be checked)
+          , bootcheck ? false
+          # whether to check if the kernel or initrd changed since boot
+          , rebootcheck ? false
+          # "builtin" to compare the systems ourselves, or "nixos-needsreboot" to use the verdict that
+          # tool leaves in /run/reboot-required when it's run from an activation script
+          , rebootbackend ? "builtin"
+          # more things the builtin reboot check compares, either two paths by what they resolve to,
+          # e.g. { name = "firmware"; booted_path = "/run/booted-system/firmware";
+          # current_path = "/run/current-system/firmware"; }, or by what a program prints when it's
+          # given each system as its argument, e.g. { name = "microcode"; command = "${script}"; }
+          , rebootprobes ? [ ]
+          # whether the reboot check keeps /run/reboot-required(.pkgs) up to date (needs to be
+          # able to write to /run), and whether to report those files when something else wrote them
+          , writerebootrequired ? false
+          , readrebootrequired ? false
+          # whether to list systemd units the current system added or removed compared to the booted
+          # one, which only start or stop for good on reboot
+          , unitcheck ? false
+          # while a reboot is pending, whether to point out that the booted system is no longer a
+          # generation (nix-collect-garbage -d) or no longer a gc root, so the next garbage collection
+          # would delete what's running
+          , gcrootscheck ? false
+          # how many days a reboot may stay pending before the block escalates, like the lock's age,
+          # e.g. { threshold = 7; } (good and update default to 1 and 3), null to not keep track;
+          # shown as "reboot pending for 5d"
+          , rebootthresholds ? null
+          # files under /etc to check still are what the current system ships, e.g.
+          # [ "ssh/sshd_config" "hosts" ], to catch ones replaced by hand or a switch that didn't apply
+          , etcpaths ? [ ]
+          # whether to list failed system and user units, or runit/s6 services that are down but
+          # meant to be up (never with noexec, besides on runit)
+          , failedunitscheck ? false
+          # an OpenTelemetry collector to send a span for each check run to over OTLP/HTTP with
+          # JSON, like "http://localhost:4318" (plain http only), from --wrap and publish only
+          , otlpendpoint ? null
+          # start the text with the least disruptive thing that makes everything pending take
+          # effect: "relog" if only your own processes run deleted code, "restart services" (or
+          # soft-reboot) for system ones, changed units and the nix-daemon, or "reboot"
+          , showaction ? false
+          # show only the one thing to do next as the text: "run flake update", "switch pending
+          # config", "reboot", "restart services", "relog" or "all good" (--details --json always
+          # has it as recommendation)
+          , recommendationtext ? false
+          # whether to check with sbctl that everything on the ESP is signed
+          , securebootcheck ? false
+          # out-of-tree modules (like zfs) that the latest generation must have built for its kernel,
+          # and that the reboot check compares between the booted and current system with modinfo
+          , pairedmodules ? [ ]
+          # list paired modules that modinfo can't tell apart, and so are compared by their files, as
+          # "module changed" instead of by name
+          , genericmodulechanges ? false
+          # once more paired modules than this changed, show "N kernel modules changed" in the text
+          # instead of their names (--details still lists them), null to always name them
+          , collapsemodules ? null
+          # globs over what the checks list (module names, units, processes...), where * matches
+          # anything and ? one character: items matching ignore are left out, e.g. [ "v4l2loopback" ],
+          # and severity gives the state items count as instead of warning, e.g. { "xpad-*" = "info"; }
+          , ignore ? [ ]
+          , severity ? { }
+          # whether to look for processes still running from garbage collected store paths
+          , deletedcheck ? false
+          # how many days old the nixpkgs pinned by the flake.lock of the project the bar runs in (the
+          # direnv one, or the working directory) can be before it's pointed out, null to not check
+          , projectthreshold ? null
+          # how many days old an entry pinned in the user or system flake registry can get before
+          # it's pointed out, null to not check
+          , registrythreshold ? null
+          # the oldest nix version the system may have before it's pointed out, e.g. "2.24", null to
+          # not check
+          , nixversionfloor ? null
+          # whether to check the running nix-daemon is the system's nix, i.e. it was restarted after
+          # nix was upgraded (needs root, see privilegedchecks)
+          , nixdaemoncheck ? false
+          # "local" to run the boot file, secure boot, deleted store path and nix-daemon checks in the bar, or
+          # "published" to read what `i3status-nix-update-widget publish` last found when run as root
+          , privilegedchecks ? "local"
+          # how many hours old what publish found may get before the bar says it's stale and shows
+          # at least a warning, e.g. because its timer stopped; a few times the timer's interval
+          , publishedmaxhours ? 6
+          # where to keep state instead of /var/lib/i3status-nix-update-widget (what publish and
+          # write-marker write) and ~/.local/state/i3status-nix-update-widget (hook and lock history),
+          # e.g. somewhere under /persist on an impermanent root; they're created when first written
+          , systemstatedir ? null
+          , userstatedir ? null
+          # never write anything (same as passing --stateless), e.g. for a kiosk or live ISO: no
+          # lock history, no hooks, no reboot-required files, and publish and write-marker fail
+          , stateless ? false
+          # for hardened services and AppArmor/SELinux profiles: implies noexec and stateless, so the
+          # widget only reads the lock date baked in, the system under /run and /proc, and only
+          # writes its block to stdout (it never uses the network either way, otlpendpoint is
+          # ignored). run `i3status-nix-update-widget doctor` under the same restrictions to see
+          # what's denied
+          , restricted ? false
+          # never spawn anything (same as passing --no-exec), e.g. for a tight systemd sandbox;
+          # the secure boot check then only looks for signatures on the ESP instead of asking sbctl,
+          # as it also does when sbctl isn't installed
+          , noexec ? false
+          # commands run with the status as JSON on stdin when the state changes, when a reboot
+          # becomes needed, and when the widget is rebuilt with a newer lock (never with noexec)
+          , onstatechange ? null
+          , onrebootneeded ? null
+          , onupdatedetected ? null
+          # hours after which onstatechange fires again while the state stays Critical, and
+          # onrebootneeded while the reboot is still pending, e.g. { critical = 6; reboot = 24; }
+          , renotify ? { }
+          # the least severe state that's shown at all, "good", "info", "warning" or "critical"; below
+          # it the block is left empty, which i3status-rust hides with hide_when_empty = true, and
+          # finding items less severe than it (see severity) are left out everywhere
+          , minseverity ? "good"
+          # how many items a check lists before it's summarised as a count (see --details for all of them)
+          , inlinelimit ? 3
+          # the longest the bar text may get before it's cut off, 0 for no limit
+          , maxwidth ? 0
+          # colours for the states in the py3status format, and the i3bar format with i3barcolors:
+          # "default", "gruvbox", "catppuccin", "nord", "solarized" or "none" (also forced by setting
+          # NO_COLOR)
+          , theme ? "default"
+          # colour the text and border of i3bar blocks from the theme, rather than leaving them to
+          # the bar's own look
+          , i3barcolors ? false
+          # "i3status-rs" for an i3status-rust custom block, "i3bar" for a plain i3bar protocol block,
+          # "py3status" for a py3status/i3pystatus composite, or "waybar" for a waybar custom module
+          # (return-type = "json") whose percentage is how far the age is towards threshold
+          , format ? "i3status-rs"
+          # i3bar only: the block's name and instance, which click events are matched against
+          , name ? "nix-update"
+          , instance ? null
+          # i3bar only: text the block is always at least as wide as, e.g. "Age: 00"
+          , minwidth ? null
+          # i3bar only: "left", "center" or "right" within minwidth
+          , align ? null
+          # i3bar only: pixels of gap after the block
+          , separatorblockwidth ? null
+          # how the i3status-rs block writes its state: "variant" ("Critical"), "lowercase"
+          # ("critical"), "numeric" (0 for good up to 3 for critical), or your own strings, e.g.
+          # { info = "#ffffff"; good = "#00ff00"; warning = "#ffff00"; critical = "#ff0000"; }
+          , statenames ? "variant"
+          # fields of the emitted block to rename, e.g. { text = "full_text"; }, for scripts that
+          # expect other names
+          , fieldnames ? { }
+          # extra fields to add to the emitted block, e.g. { name = "nix-update"; instance = "desktop"; }
+          , extrafields ? { }
+          # --wrap only: where among the wrapped command's blocks ours goes, and how many seconds
+          # to reuse it for before running the checks again
+          , wrapposition ? 0
+          , wrapinterval ? 300
+          # --wrap only: days past threshold after which a Critical block blinks by toggling urgent on
+          # every status line, null to never blink
+          , blinkafter ? null
+          # remote only: how many hosts to ask at once, and how many seconds each gets to answer
+          , remoteparallel ? 8
+          , remotetimeout ? 20
+          # --wrap only: leave the block as it is while the logind session is idle or locked
+          # (swayidle's idlehint sets that), and run the checks again as soon as it's back
+          , idleaware ? false
           }:
           with pkgs;
           let
@@ -58,16 +257,147 @@
             # and we're going to ignore that sometimes flakes just don't receive updates because nixpkgs is being constantly updated
             # default to a really old lockfile content (1s unix timestamp) so it's obvious if you forget to override!
             lockfile = if flakelock != null then builtins.fromJSON (builtins.readFile flakelock) else { nodes.nixpkgs.locked.lastModified = 1; };
-            recenttime = builtins.head (lib.sort (a: b: a > b)
-              (map (key: lockfile.nodes.${key}.locked.lastModified or 0)
-                (lib.attrNames lockfile.nodes)));
+            newest = lock: builtins.head (lib.sort (a: b: a > b)
+              (map (key: lock.nodes.${key}.locked.lastModified or 0)
+                (lib.attrNames lock.nodes)));
+            recenttime = newest lockfile;
+
+            # every input with a lastModified, for the per-input metrics
+            inputdates = lib.mapAttrsToList
+              (name: node: "(\"${name}\", ${toString node.locked.lastModified})")
+              (lib.filterAttrs (name: node: node ? locked.lastModified) lockfile.nodes);
+
+            optionalStr = s: if s == null then "None" else "Some(\"${s}\")";
+
+            scheduleWindow = w: ''
+              Window {
+                weekdays: &[${lib.concatMapStringsSep ", " (d: "chrono::Weekday::${d}") w.days}],
+                hours: ${toString w.from}..${toString w.to},
+                thresholds: Thresholds {
+                  good: ${toString (w.good or 3)},
+                  update: ${toString (w.update or 4)},
+                  out_of_date: ${toString w.threshold},
+                },
+              }'';
+
+            trackedFlake = f: ''
+              Tracked {
+                name: "${f.name}",
+                modified_date: ${toString (newest (builtins.fromJSON (builtins.readFile f.flakelock)))},
+                thresholds: Thresholds {
+                  good: ${toString (f.good or 3)},
+                  update: ${toString (f.update or 4)},
+                  out_of_date: ${toString (f.threshold or 14)},
+                },
+              }'';
+
+            rebootProbe = p: if p ? command
+              then "Probe::Command { name: \"${p.name}\", command: \"${p.command}\" }"
+              else "Probe::Paths { name: \"${p.name}\", booted: \"${p.booted_path}\", current: \"${p.current_path}\" }";
+
+            stateVariant = s: "State::${{ good = "Good"; info = "Info"; warning = "Warning"; critical = "Critical"; }.${s}}";
+
+            stateNames =
+              if builtins.isAttrs statenames then ''
+                StateNames::Custom {
+                  info: "${statenames.info}",
+                  good: "${statenames.good}",
+                  warning: "${statenames.warning}",
+                  critical: "${statenames.critical}",
+                }''
+              else {
+                variant = "StateNames::Variant";
+                lowercase = "StateNames::Lowercase";
+                numeric = "StateNames::Numeric";
+              }.${statenames};
 
             config_file = pkgs.writeText "modified_data.rs" ''
               const MODIFIED_DATE: i64 = ${toString recenttime};
+              const INPUT_DATES: &[(&str, i64)] = &[${lib.concatStringsSep ", " inputdates}];
+              const TRACKED_FLAKES: &[Tracked] = &[${lib.concatMapStringsSep ", " trackedFlake trackedflakes}];
               const GOOD_THRESHOLD: i64 = 3;
               const UPDATE_THRESHOLD: i64 = 4;
               const OUT_OF_DATE_THRESHOLD: i64 = ${toString threshold};
               const STATUS_ICON: &str = "${icon}";
+              const TIMEZONE: &str = "${timezone}";
+              const CALENDAR_DAYS: bool = ${lib.boolToString calendardays};
+              const MAINTENANCE_CALENDAR: Option<&str> = ${optionalStr maintenancecalendar};
+              const INSTALL_GRACE_DAYS: Option<i64> = ${if installgracedays == null then "None" else "Some(${toString installgracedays})"};
+              const DEPLOYED_BY: Option<&str> = ${optionalStr deployedby};
+              const DEPLOY_MARKER: Option<&str> = ${optionalStr deploymarker};
+              const SHOW_UPDATE_DAYS: Option<i64> = ${if showupdatedays == null then "None" else "Some(${toString showupdatedays})"};
+              const AGE_DISPLAY: &[&str] = &[${lib.concatMapStringsSep ", " (p: "\"${p}\"") agedisplay}];
+              const AGE_LANGUAGE: Option<&str> = ${optionalStr agelanguage};
+              const SHOW_CLOCK_SKEW_HINT: bool = ${lib.boolToString clockskewhint};
+              const SHOW_UPTIME: bool = ${lib.boolToString uptime};
+              const SHOW_KERNEL_AGE: bool = ${lib.boolToString kernelage};
+              const CHECK_BOOT_FILES: bool = ${lib.boolToString bootcheck};
+              const CHECK_REBOOT: bool = ${lib.boolToString rebootcheck};
+              const REBOOT_BACKEND: &str = "${rebootbackend}";
+              const REBOOT_PROBES: &[Probe] = &[${lib.concatMapStringsSep ", " rebootProbe rebootprobes}];
+              const WRITE_REBOOT_REQUIRED: bool = ${lib.boolToString writerebootrequired};
+              const READ_REBOOT_REQUIRED: bool = ${lib.boolToString readrebootrequired};
+              const CHECK_UNITS: bool = ${lib.boolToString unitcheck};
+              const CHECK_GC_ROOTS: bool = ${lib.boolToString gcrootscheck};
+              const REBOOT_THRESHOLDS: Option<Thresholds> = ${if rebootthresholds == null then "None" else ''
+                Some(Thresholds {
+                  good: ${toString (rebootthresholds.good or 1)},
+                  update: ${toString (rebootthresholds.update or 3)},
+                  out_of_date: ${toString rebootthresholds.threshold},
+                })''};
+              const ETC_PATHS: &[&str] = &[${lib.concatMapStringsSep ", " (p: "\"${p}\"") etcpaths}];
+              const CHECK_FAILED_UNITS: bool = ${lib.boolToString failedunitscheck};
+              const OTLP_ENDPOINT: Option<&str> = ${optionalStr (if restricted then null else otlpendpoint)};
+              const SHOW_ACTION: bool = ${lib.boolToString showaction};
+              const RECOMMENDATION_TEXT: bool = ${lib.boolToString recommendationtext};
+              const CHECK_SECURE_BOOT: bool = ${lib.boolToString securebootcheck};
+              const PAIRED_MODULES: &[&str] = &[${lib.concatMapStringsSep ", " (m: "\"${m}\"") pairedmodules}];
+              const GENERIC_MODULE_CHANGES: bool = ${lib.boolToString genericmodulechanges};
+              const COLLAPSE_MODULES: Option<usize> = ${if collapsemodules == null then "None" else "Some(${toString collapsemodules})"};
+              const ITEM_RULES: &[Rule] = &[${lib.concatStringsSep ", " (
+                map (p: "Rule { pattern: \"${p}\", state: None }") ignore
+                ++ lib.mapAttrsToList (p: s: "Rule { pattern: \"${p}\", state: Some(${stateVariant s}) }") severity)}];
+              const CHECK_DELETED_STORE_PATHS: bool = ${lib.boolToString deletedcheck};
+              const PROJECT_THRESHOLD: Option<i64> = ${if projectthreshold == null then "None" else "Some(${toString projectthreshold})"};
+              const REGISTRY_THRESHOLD: Option<i64> = ${if registrythreshold == null then "None" else "Some(${toString registrythreshold})"};
+              const NIX_VERSION_FLOOR: Option<&str> = ${optionalStr nixversionfloor};
+              const CHECK_NIX_DAEMON: bool = ${lib.boolToString nixdaemoncheck};
+              const PRIVILEGED_CHECKS: &str = "${privilegedchecks}";
+              const PUBLISHED_MAX_HOURS: i64 = ${toString publishedmaxhours};
+              const NO_EXEC: bool = ${lib.boolToString (noexec || restricted)};
+              const STATELESS: bool = ${lib.boolToString (stateless || restricted)};
+              const SYSTEM_STATE_DIR: Option<&str> = ${optionalStr systemstatedir};
+              const USER_STATE_DIR: Option<&str> = ${optionalStr userstatedir};
+              const ON_STATE_CHANGE: Option<&str> = ${optionalStr onstatechange};
+              const ON_REBOOT_NEEDED: Option<&str> = ${optionalStr onrebootneeded};
+              const ON_UPDATE_DETECTED: Option<&str> = ${optionalStr onupdatedetected};
+              const RENOTIFY: hooks::Renotify = hooks::Renotify {
+                critical: ${if renotify ? critical then "Some(${toString renotify.critical})" else "None"},
+                reboot: ${if renotify ? reboot then "Some(${toString renotify.reboot})" else "None"},
+              };
+              const MIN_SEVERITY: State = ${stateVariant minseverity};
+              const INLINE_LIMIT: usize = ${toString inlinelimit};
+              const MAX_WIDTH: usize = ${toString maxwidth};
+              const OUTPUT_FORMAT: &str = "${format}";
+              const I3BAR_NAME: Option<&str> = ${optionalStr name};
+              const I3BAR_INSTANCE: Option<&str> = ${optionalStr instance};
+              const I3BAR_MIN_WIDTH: Option<&str> = ${optionalStr minwidth};
+              const I3BAR_ALIGN: Option<&str> = ${optionalStr align};
+              const I3BAR_SEPARATOR_BLOCK_WIDTH: Option<u32> = ${if separatorblockwidth == null then "None" else "Some(${toString separatorblockwidth})"};
+              const STATE_NAMES: StateNames = ${stateNames};
+              const FIELD_NAMES: &[(&str, &str)] = &[${lib.concatStringsSep ", " (lib.mapAttrsToList (from: to: "(\"${from}\", \"${to}\")") fieldnames)}];
+              const EXTRA_FIELDS: &str = r#"${builtins.toJSON extrafields}"#;
+              const WRAP_POSITION: usize = ${toString wrapposition};
+              const WRAP_INTERVAL: u64 = ${toString wrapinterval};
+              const REMOTE_PARALLEL: usize = ${toString remoteparallel};
+              const REMOTE_TIMEOUT: u64 = ${toString remotetimeout};
+              const BLINK_AFTER: Option<i64> = ${if blinkafter == null then "None" else "Some(${toString blinkafter})"};
+              const IDLE_AWARE: bool = ${lib.boolToString idleaware};
+              const THRESHOLD_SCHEDULE: &[Window] = &[${lib.concatMapStringsSep ", " scheduleWindow schedule}];
+              const THEME: &str = "${theme}";
+              const I3BAR_COLORS: bool = ${lib.boolToString i3barcolors};
+              const SBCTL: &str = "${if securebootcheck then "${sbctl}/bin/sbctl" else "sbctl"}";
+              const MODINFO: &str = "${if rebootcheck && pairedmodules != [ ] then "${kmod}/bin/modinfo" else "modinfo"}";
             '';
 
             prePatch = ''
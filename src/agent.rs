@@ -0,0 +1,177 @@
+// a tiny protocol for `remote` to ask a server about itself over `ssh host ... agent`: a JSON
+// request per line on stdin, a JSON reply per line on stdout. the status is the same as
+// `--details --json`, but wrapped with a protocol version so neither side has to guess. fleets
+// aren't upgraded all at once, so each side speaks the highest version both know, and a build
+// from before there were agents still gets its bar block read.
+
+use crate::State;
+use std::io::{BufRead, Write};
+
+/// Bumped whenever a request or reply changes in a way the other side can't ignore. Fields can be
+/// added without a bump, as both sides skip ones they don't know.
+pub const PROTOCOL: u32 = 1;
+/// The oldest protocol still answered.
+pub const MIN_PROTOCOL: u32 = 1;
+
+/// A question for the agent.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct Request {
+    /// The highest protocol the asking side speaks.
+    pub protocol: u32,
+    pub query: String,
+}
+
+/// The agent's answer, with the status or why there isn't one.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+pub struct Reply {
+    /// The protocol the reply is in, the highest both sides speak.
+    pub protocol: u32,
+    /// The oldest and newest protocols the agent speaks, left out by agents from before it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supported: Option<(u32, u32)>,
+    /// The crate version of the agent, for the curious; nothing depends on it.
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The line asking an agent for its status.
+pub fn request() -> String {
+    let request = Request {
+        protocol: PROTOCOL,
+        query: "status".to_string(),
+    };
+    serde_json::to_string(&request).expect("a request serializes")
+}
+
+/// The protocol to answer a request for `requested` in, `None` if it's too old to be answered.
+pub fn negotiate(requested: u32) -> Option<u32> {
+    (requested >= MIN_PROTOCOL).then_some(requested.min(PROTOCOL))
+}
+
+/// The reply to one request line, calling `status` for the `--details --json` if it's asked for.
+pub fn answer(line: &str, status: impl FnOnce() -> serde_json::Value) -> Reply {
+    let mut reply = Reply {
+        protocol: PROTOCOL,
+        supported: Some((MIN_PROTOCOL, PROTOCOL)),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        status: None,
+        error: None,
+    };
+    let request = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request,
+        Err(err) => {
+            reply.error = Some(format!("bad request: {err}"));
+            return reply;
+        }
+    };
+    let Some(protocol) = negotiate(request.protocol) else {
+        reply.error = Some(format!(
+            "protocol {} is too old, this agent speaks {MIN_PROTOCOL} to {PROTOCOL}",
+            request.protocol
+        ));
+        return reply;
+    };
+    reply.protocol = protocol;
+    match request.query.as_str() {
+        "status" => reply.status = Some(status()),
+        query => reply.error = Some(format!("unknown query {query:?}")),
+    }
+    reply
+}
+
+/// Answer each line of `input` on `output` until it ends.
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut status: impl FnMut() -> serde_json::Value,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = answer(&line, &mut status);
+        writeln!(output, "{}", serde_json::to_string(&reply)?)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// The state a bar block was printed with, by any of the built in `StateNames`. Custom names are
+/// only known to the build that has them, so those aren't recognised.
+fn block_state(state: &serde_json::Value) -> Option<State> {
+    if let Some(level) = state.as_u64() {
+        return [State::Good, State::Info, State::Warning, State::Critical]
+            .get(usize::try_from(level).ok()?)
+            .copied();
+    }
+    match state.as_str()?.to_lowercase().as_str() {
+        "good" => Some(State::Good),
+        "info" => Some(State::Info),
+        "warning" => Some(State::Warning),
+        "critical" => Some(State::Critical),
+        _ => None,
+    }
+}
+
+/// What a build from before the agent prints instead, having taken `agent` for no argument at all,
+/// as a `--details --json` status: its bar block in whichever output format it was built with. The
+/// state comes from i3status-rust's `state` or waybar's `class`, and i3bar and py3status blocks
+/// only have `urgent` to go by; a state that can't be told is Info.
+fn old_block(line: &str) -> Option<String> {
+    let block = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    // py3status's composite, one part
+    let block = match block.as_array() {
+        Some(parts) => parts.first()?.clone(),
+        None => block,
+    };
+    let text = block
+        .get("text")
+        .or_else(|| block.get("full_text"))?
+        .as_str()?;
+    let state = block
+        .get("state")
+        .or_else(|| block.get("class"))
+        .and_then(block_state)
+        .or_else(|| {
+            let urgent = block.get("urgent")?.as_bool()?;
+            urgent.then_some(State::Critical)
+        })
+        .unwrap_or(State::Info);
+    Some(
+        serde_json::json!({
+            "state": state,
+            "cause": "no agent",
+            "text": text,
+        })
+        .to_string(),
+    )
+}
+
+/// The status in what an agent printed back, as `--details --json`. A reply in a protocol this
+/// side doesn't speak is refused, other agents' extra fields are skipped, and an old build's bar
+/// block stands in for its status.
+pub fn status(output: &str) -> std::result::Result<String, String> {
+    let line = output
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    let reply = match serde_json::from_str::<Reply>(line) {
+        Ok(reply) => reply,
+        Err(err) => return old_block(line).ok_or(format!("not an agent reply: {err}")),
+    };
+    if !(MIN_PROTOCOL..=PROTOCOL).contains(&reply.protocol) {
+        return Err(format!(
+            "agent {} answered in protocol {}, this side speaks {MIN_PROTOCOL} to {PROTOCOL}",
+            reply.version, reply.protocol
+        ));
+    }
+    match (reply.status, reply.error) {
+        (Some(status), _) => Ok(status.to_string()),
+        (None, Some(err)) => Err(format!("agent {}: {err}", reply.version)),
+        (None, None) => Err(format!("agent {} sent no status", reply.version)),
+    }
+}
@@ -0,0 +1,69 @@
+// for a handful of hosts, the `--details --json` of each (collected with scp or from CI) is all a
+// fleet overview needs: rank them worst first.
+
+use crate::State;
+use std::path::Path;
+
+/// One host's `--details --json`, or what could be made of it.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HostStatus {
+    /// Not in the JSON itself, taken from the file name.
+    #[serde(default)]
+    pub host: String,
+    pub state: State,
+    pub cause: String,
+    #[serde(default)]
+    pub reasons: Vec<String>,
+    pub text: String,
+}
+
+/// The status in `path`, for the host the file is named after (`web1.json` is web1). A file that
+/// can't be read or parsed counts as Critical, as nothing is known about that host.
+pub fn load(path: &Path) -> HostStatus {
+    let host = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    parse(
+        host,
+        std::fs::read_to_string(path).map_err(|err| err.to_string()),
+    )
+}
+
+/// The status of `host` from its `--details --json`, or Critical with why it couldn't be had.
+pub fn parse(host: String, json: std::result::Result<String, String>) -> HostStatus {
+    let parsed = json
+        .and_then(|json| serde_json::from_str::<HostStatus>(&json).map_err(|err| err.to_string()));
+    match parsed {
+        Ok(status) => HostStatus { host, ..status },
+        Err(err) => HostStatus {
+            host,
+            state: State::Critical,
+            cause: "unreadable".to_string(),
+            reasons: vec!["CHECK_FAILED".to_string()],
+            text: err,
+        },
+    }
+}
+
+/// `hosts` worst first, keeping their order among equals.
+pub fn rank(mut hosts: Vec<HostStatus>) -> Vec<HostStatus> {
+    hosts.sort_by_key(|host| std::cmp::Reverse(host.state));
+    hosts
+}
+
+/// One line per host, with the columns lined up.
+pub fn table(hosts: &[HostStatus]) -> String {
+    let width = hosts.iter().map(|host| host.host.len()).max().unwrap_or(0);
+    hosts
+        .iter()
+        .map(|host| {
+            format!(
+                "{:width$}  {:8}  {}\n",
+                host.host,
+                format!("{:?}", host.state),
+                host.text
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,261 @@
+// checks that what's on the boot partition is what the latest generation says it should be.
+// a switch can succeed while the copy onto the firmware partition or ESP silently doesn't happen,
+// and `nixos-rebuild boot` can leave the default entry pointing somewhere else.
+
+use crate::error::{IoContext, Result};
+use std::path::{Path, PathBuf};
+
+/// Where the bootloaders' configs live, each under its own path below.
+pub const BOOT: &str = "/boot";
+
+/// Where NixOS' generic extlinux builder puts its menu.
+pub const EXTLINUX_CONF: &str = "extlinux/extlinux.conf";
+
+/// Where NixOS' systemd-boot builder puts the loader config.
+pub const SYSTEMD_BOOT_LOADER_CONF: &str = "loader/loader.conf";
+
+/// Where NixOS' GRUB builder puts its menu, with the saved entry in `grubenv` next to it.
+pub const GRUB_CFG: &str = "grub/grub.cfg";
+
+/// The newest system generation, which is what the bootloader should default to.
+pub const SYSTEM_PROFILE: &str = "/nix/var/nix/profiles/system";
+
+/// The boot files the default extlinux entry points at.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExtlinuxEntry {
+    pub kernel: Option<PathBuf>,
+    pub initrd: Option<PathBuf>,
+    /// The system toplevel from the `init=` kernel parameter.
+    pub system: Option<PathBuf>,
+}
+
+/// Find the kernel, initrd and system of the DEFAULT entry in an extlinux.conf.
+/// Relative paths are resolved against `dir`, the directory the config lives in.
+pub fn parse_extlinux_default(conf: &str, dir: &Path) -> ExtlinuxEntry {
+    let default = conf.lines().find_map(|line| {
+        let (key, value) = split_directive(line)?;
+        key.eq_ignore_ascii_case("DEFAULT").then_some(value)
+    });
+
+    let mut entry = ExtlinuxEntry::default();
+    let mut in_default = false;
+
+    for line in conf.lines() {
+        let Some((key, value)) = split_directive(line) else {
+            continue;
+        };
+
+        match key.to_ascii_uppercase().as_str() {
+            "LABEL" => in_default = Some(value) == default,
+            "LINUX" | "KERNEL" if in_default => entry.kernel = Some(dir.join(value)),
+            "INITRD" if in_default => entry.initrd = Some(dir.join(value)),
+            "APPEND" if in_default => entry.system = init_system(value),
+            _ => {}
+        }
+    }
+
+    entry
+}
+
+fn split_directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once(char::is_whitespace)?;
+    Some((key, value.trim()))
+}
+
+/// The system toplevel a kernel command line boots into, taken from its `init=` parameter.
+fn init_system(kernel_params: &str) -> Option<PathBuf> {
+    kernel_params
+        .split_whitespace()
+        .find_map(|param| param.strip_prefix("init="))
+        .and_then(|init| Path::new(init).parent())
+        .map(Path::to_path_buf)
+}
+
+/// Which of the default entry, kernel and initrd on the boot partition `boot` don't belong to
+/// `system_profile`, using whichever bootloader config is present. Nothing is stale when there's
+/// no bootloader config known here, e.g. in a container, or the default can't be told.
+pub fn stale_boot_files(boot: &Path, system_profile: &Path) -> Result<Vec<String>> {
+    let default_entry = |is_latest: Option<bool>| match is_latest {
+        Some(false) => vec!["default entry".to_string()],
+        _ => vec![],
+    };
+
+    let loader_conf = boot.join(SYSTEMD_BOOT_LOADER_CONF);
+    if loader_conf.exists() {
+        return Ok(default_entry(systemd_boot_default_is_latest(
+            &loader_conf,
+            system_profile,
+        )?));
+    }
+    let grub_cfg = boot.join(GRUB_CFG);
+    if grub_cfg.exists() {
+        return Ok(default_entry(grub_default_is_latest(
+            &grub_cfg,
+            system_profile,
+        )?));
+    }
+    let extlinux_conf = boot.join(EXTLINUX_CONF);
+    if extlinux_conf.exists() {
+        return stale_extlinux_files(&extlinux_conf, system_profile);
+    }
+    Ok(vec![])
+}
+
+/// Names of the parts of the default extlinux entry that don't match `system_profile`.
+pub fn stale_extlinux_files(extlinux_conf: &Path, system_profile: &Path) -> Result<Vec<String>> {
+    let conf = std::fs::read_to_string(extlinux_conf)
+        .system_context(|| format!("Could not read {}", extlinux_conf.display()))?;
+    let dir = extlinux_conf.parent().unwrap_or(Path::new("/"));
+
+    let entry = parse_extlinux_default(&conf, dir);
+
+    let mut stale = Vec::new();
+    if !is_same_system(entry.system.as_deref(), system_profile)? {
+        stale.push("default entry".to_string());
+    }
+    for (name, on_boot) in [("kernel", entry.kernel), ("initrd", entry.initrd)] {
+        let Some(on_boot) = on_boot else {
+            continue;
+        };
+        if !same_contents(&on_boot, &system_profile.join(name))? {
+            stale.push(name.to_string());
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Whether the `default` entry in systemd-boot's loader.conf boots `system_profile`, `None` if it's
+/// a glob or `@saved`, which only the loader itself resolves.
+pub fn systemd_boot_default_is_latest(
+    loader_conf: &Path,
+    system_profile: &Path,
+) -> Result<Option<bool>> {
+    let conf = std::fs::read_to_string(loader_conf)
+        .system_context(|| format!("Could not read {}", loader_conf.display()))?;
+
+    let Some(default) = conf.lines().find_map(|line| {
+        let (key, value) = split_directive(line)?;
+        (key == "default").then_some(value)
+    }) else {
+        return Ok(Some(false));
+    };
+    if default.starts_with('@') || default.contains(['*', '?', '[']) {
+        return Ok(None);
+    }
+
+    let entry_name = if default.ends_with(".conf") {
+        default.to_string()
+    } else {
+        format!("{default}.conf")
+    };
+    let entries = loader_conf
+        .parent()
+        .unwrap_or(Path::new("/"))
+        .join("entries");
+
+    // the ESP being out of sync can mean the entry the default names isn't there at all
+    let Ok(entry) = std::fs::read_to_string(entries.join(entry_name)) else {
+        return Ok(Some(false));
+    };
+
+    let system = entry.lines().find_map(|line| {
+        let (key, value) = split_directive(line)?;
+        (key == "options").then(|| init_system(value)).flatten()
+    });
+
+    is_same_system(system.as_deref(), system_profile).map(Some)
+}
+
+/// Whether the default entry in a grub.cfg boots `system_profile`, following `${saved_entry}` into
+/// the `grubenv` next to it. `None` if the default is something else only GRUB can work out, or
+/// names an entry that isn't there (GRUB then falls back to the first one).
+pub fn grub_default_is_latest(grub_cfg: &Path, system_profile: &Path) -> Result<Option<bool>> {
+    let conf = std::fs::read_to_string(grub_cfg)
+        .system_context(|| format!("Could not read {}", grub_cfg.display()))?;
+    let unquote = |value: &str| value.trim().trim_matches(['"', '\'']).to_string();
+
+    // NixOS' grub.cfg first sets a one-off `grub-reboot` entry if there is one, that's not the
+    // default that stays
+    let default = conf
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("set default="))
+        .map(unquote)
+        .find(|default| default != "${next_entry}")
+        .unwrap_or_else(|| "0".to_string());
+    let default = if default == "${saved_entry}" {
+        let grubenv = grub_cfg.with_file_name("grubenv");
+        // no saved entry yet means GRUB boots the first one
+        std::fs::read_to_string(grubenv)
+            .ok()
+            .and_then(|env| {
+                env.lines()
+                    .find_map(|line| line.strip_prefix("saved_entry="))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "0".to_string())
+    } else {
+        default
+    };
+
+    // only top level entries count towards the index, a submenu of older generations is one
+    let mut depth = 0usize;
+    let mut entries = Vec::new();
+    for line in conf.lines().map(str::trim) {
+        if line.starts_with("menuentry ") || line.starts_with("submenu ") {
+            if depth == 0 {
+                entries.push((line, None));
+            }
+            depth += 1;
+        } else if line == "}" {
+            depth = depth.saturating_sub(1);
+        } else if depth == 1 && line.starts_with("linux ") {
+            if let Some((_, system)) = entries.last_mut() {
+                *system = init_system(line);
+            }
+        }
+    }
+
+    let entry = match default.parse::<usize>() {
+        Ok(index) => entries.get(index),
+        Err(_) => entries.iter().find(|(header, _)| {
+            header
+                .strip_prefix("menuentry ")
+                .is_some_and(|header| header.starts_with(&format!("\"{default}\"")))
+                || header.contains(&format!("--id {default} "))
+        }),
+    };
+    let Some((header, system)) = entry else {
+        return Ok(None);
+    };
+    if header.starts_with("submenu ") {
+        return Ok(Some(false));
+    }
+    is_same_system(system.as_deref(), system_profile).map(Some)
+}
+
+fn is_same_system(system: Option<&Path>, system_profile: &Path) -> Result<bool> {
+    let Some(system) = system else {
+        return Ok(false);
+    };
+    let latest = std::fs::canonicalize(system_profile)
+        .system_context(|| format!("Could not resolve {}", system_profile.display()))?;
+
+    // a system that no longer exists certainly isn't the latest one
+    Ok(std::fs::canonicalize(system).is_ok_and(|system| system == latest))
+}
+
+fn same_contents(on_boot: &Path, expected: &Path) -> Result<bool> {
+    // a boot file that's gone missing is as stale as it gets
+    let Ok(on_boot) = std::fs::read(on_boot) else {
+        return Ok(false);
+    };
+    let expected = std::fs::read(expected)
+        .system_context(|| format!("Could not read {}", expected.display()))?;
+
+    Ok(on_boot == expected)
+}
@@ -0,0 +1,54 @@
+// what `report` adds besides the settings and a run of the checks: what earlier runs left behind,
+// the state directories and the widget's own log lines in the journal, since bugs that depend on
+// the environment often only show across runs.
+
+use crate::error::{Error, IoContext, Result};
+use std::path::Path;
+
+/// Every file kept in the state directory `dir` with its contents, the lock file aside, sorted by
+/// name. Nothing there is secret, it's only what the widget wrote itself.
+pub fn state_snapshot(dir: &Path) -> String {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return format!("{}: not there\n", dir.display());
+    };
+    let mut files = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && !path.ends_with(".lock"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut snapshot = format!("{}:\n", dir.display());
+    if files.is_empty() {
+        snapshot.push_str("  nothing kept\n");
+    }
+    for file in files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        match std::fs::read_to_string(&file) {
+            Ok(contents) => snapshot.push_str(&format!("  {name}: {}\n", contents.trim())),
+            Err(err) => snapshot.push_str(&format!("  {name}: {err}\n")),
+        }
+    }
+    snapshot
+}
+
+/// The last `lines` lines the widget logged to the journal, which is where they go when it runs as
+/// a systemd service. Run from a bar, its stderr goes wherever the bar sends it instead.
+pub fn journal_lines(journalctl: &str, lines: usize) -> Result<String> {
+    let output = std::process::Command::new(journalctl)
+        .args(["--no-pager", "--output", "short-iso", "--lines"])
+        .arg(lines.to_string())
+        .args(["--identifier", "i3status-nix-update-widget"])
+        .output()
+        .tool_context(|| format!("Could not run {journalctl}"))?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "{journalctl} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,23 @@
+// hosts managed by colmena, deploy-rs or morph are updated from elsewhere, so telling them to run
+// nixos-rebuild is wrong. what they wait for is the next deploy.
+
+use crate::error::{IoContext, Result};
+use std::path::Path;
+
+/// Unix time `marker` was last touched, which deploy hooks do on every deploy. `None` if it
+/// doesn't exist (yet).
+pub fn deployed_at(marker: &Path) -> Result<Option<i64>> {
+    match std::fs::metadata(marker) {
+        Ok(meta) => Ok(Some(std::os::unix::fs::MetadataExt::mtime(&meta))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).system_context(|| format!("Could not read {}", marker.display())),
+    }
+}
+
+/// What an out of date host managed by `tool` is waiting for, e.g. "awaiting colmena deploy".
+pub fn awaiting(tool: Option<&str>) -> String {
+    match tool {
+        Some(tool) => format!("awaiting {tool} deploy"),
+        None => "awaiting deploy".to_string(),
+    }
+}
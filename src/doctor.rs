@@ -0,0 +1,48 @@
+// under systemd hardening, AppArmor or SELinux some of what the checks need is simply denied, and
+// the checks then fail in ways that look like bugs. these say up front what the environment allows.
+
+use std::path::Path;
+
+/// Why `path` can't be read (listed, if it's a directory), or `None` if it can.
+pub fn read_denied(path: &Path) -> Option<String> {
+    let result = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::read_dir(path).map(drop),
+        Ok(_) => std::fs::read(path).map(drop),
+        Err(err) => Err(err),
+    };
+    result.err().map(|err| err.to_string())
+}
+
+/// Why nothing can be created in `dir` (or `dir` itself can't be, if it doesn't exist yet), or
+/// `None` if it can. Leaves nothing behind either way.
+pub fn write_denied(dir: &Path) -> Option<String> {
+    // the nearest directory that exists is where creating it would have to start
+    let existing = dir.ancestors().find(|dir| dir.exists())?;
+    let probe = existing.join(format!(".doctor.{}.tmp", std::process::id()));
+    match std::fs::write(&probe, "") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            None
+        }
+        Err(err) => Some(err.to_string()),
+    }
+}
+
+/// Whether a program could be run.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Exec {
+    Allowed,
+    /// Not there to run, which says nothing about whether running programs is allowed.
+    Missing,
+    Denied(String),
+}
+
+/// Try running `program` with `args`, given by its path so a minimal `PATH` can't make a missing
+/// program look like a denial.
+pub fn try_exec(program: &Path, args: &[&str]) -> Exec {
+    match std::process::Command::new(program).args(args).output() {
+        Ok(_) => Exec::Allowed,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Exec::Missing,
+        Err(err) => Exec::Denied(err.to_string()),
+    }
+}
@@ -0,0 +1,84 @@
+// the library reports failures by where they came from, so whoever embeds it can tell a broken
+// config apart from a system that just can't be read right now.
+
+/// Everything that can go wrong in the library.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The build-time configuration doesn't make sense.
+    #[error("{0}")]
+    Config(String),
+    /// The lock date baked in from flake.lock is unusable.
+    #[error("{0}")]
+    FlakeLock(String),
+    /// Something about the running or installed system couldn't be read or made sense of.
+    #[error("{context}")]
+    SystemProfile {
+        context: String,
+        #[source]
+        source: Option<std::io::Error>,
+    },
+    /// An external program couldn't be run or didn't succeed.
+    #[error("{context}")]
+    ExternalTool {
+        context: String,
+        #[source]
+        source: Option<std::io::Error>,
+    },
+    /// Something elsewhere on the network couldn't be reached or didn't take what was sent.
+    #[error("{context}")]
+    Network {
+        context: String,
+        #[source]
+        source: Option<std::io::Error>,
+    },
+    /// The state directory or another file we maintain couldn't be written.
+    #[error("{context}")]
+    State {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Could not serialize output")]
+    Serialize(#[from] serde_json::Error),
+    #[error("Could not write output")]
+    Output(#[from] std::io::Error),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Attach context to IO errors while picking which kind of failure they are.
+pub(crate) trait IoContext<T> {
+    fn system_context(self, context: impl FnOnce() -> String) -> Result<T>;
+    fn tool_context(self, context: impl FnOnce() -> String) -> Result<T>;
+    fn state_context(self, context: impl FnOnce() -> String) -> Result<T>;
+    fn network_context(self, context: impl FnOnce() -> String) -> Result<T>;
+}
+
+impl<T> IoContext<T> for std::io::Result<T> {
+    fn system_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| Error::SystemProfile {
+            context: context(),
+            source: Some(source),
+        })
+    }
+
+    fn tool_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| Error::ExternalTool {
+            context: context(),
+            source: Some(source),
+        })
+    }
+
+    fn state_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| Error::State {
+            context: context(),
+            source,
+        })
+    }
+    fn network_context(self, context: impl FnOnce() -> String) -> Result<T> {
+        self.map_err(|source| Error::Network {
+            context: context(),
+            source: Some(source),
+        })
+    }
+}
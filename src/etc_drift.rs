@@ -0,0 +1,26 @@
+// activation links /etc entries to the current system's etc. if one was replaced by hand, or
+// activation gave up halfway, the switch quietly didn't take effect for that file.
+
+use std::path::Path;
+
+pub const LIVE_ETC: &str = "/etc";
+
+/// Which of `paths` (relative to /etc) under `live` no longer match what `system_etc` ships. Files
+/// that activation copies instead of linking count as matching if their contents are the same.
+/// Paths the system doesn't ship are left alone.
+pub fn drifted(live: &Path, system_etc: &Path, paths: &[&str]) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| {
+            let Ok(expected) = std::fs::canonicalize(system_etc.join(path)) else {
+                return false;
+            };
+            match std::fs::canonicalize(live.join(path)) {
+                Ok(actual) if actual == expected => false,
+                Ok(actual) => std::fs::read(actual).ok() != std::fs::read(expected).ok(),
+                Err(_) => true,
+            }
+        })
+        .map(|path| path.to_string())
+        .collect()
+}
@@ -0,0 +1,38 @@
+// a switch that leaves units failed is easy to miss when nothing on screen says so.
+
+use crate::error::{Error, IoContext, Result};
+
+/// Unit names from `systemctl --failed --no-legend --plain` output.
+pub fn parse(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.trim_start_matches(['●', ' '])
+                .split_whitespace()
+                .next()
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// The failed units of the system, or of the user's manager with `user`.
+pub fn failed(user: bool) -> Result<Vec<String>> {
+    let mut command = std::process::Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let output = command
+        .args(["--failed", "--no-legend", "--plain"])
+        .output()
+        .tool_context(|| "Could not run systemctl".to_string())?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "systemctl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
@@ -0,0 +1,42 @@
+// once a switch is pending a reboot, the booted system is what's running. rebooting after garbage
+// collection took it is fine, but until then its files are only safe while something still roots it.
+
+use crate::error::{IoContext, Result};
+use std::path::Path;
+
+pub const GC_ROOTS: &str = "/nix/var/nix/gcroots";
+
+/// What no longer protects the `booted` system from garbage collection: "generation" when no
+/// `system-N-link` next to `system_profile` points at it (nix-collect-garbage -d removed it), and
+/// "gc root" when nothing directly in `gc_roots` does either.
+pub fn unprotected(booted: &Path, system_profile: &Path, gc_roots: &Path) -> Result<Vec<String>> {
+    let booted = std::fs::canonicalize(booted)
+        .system_context(|| format!("Could not resolve {}", booted.display()))?;
+    let points_at_booted = |dir: &Path, wanted: &dyn Fn(&str) -> bool| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries.filter_map(Result::ok).any(|entry| {
+                wanted(&entry.file_name().to_string_lossy())
+                    && std::fs::canonicalize(entry.path()).is_ok_and(|target| target == booted)
+            })
+        })
+    };
+
+    let mut unprotected = Vec::new();
+    let profiles = system_profile.parent().unwrap_or(Path::new("/"));
+    let profile_name = system_profile
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let is_generation = |name: &str| {
+        name.strip_prefix(&profile_name)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .is_some_and(|rest| rest.ends_with("-link"))
+    };
+    if !points_at_booted(profiles, &is_generation) {
+        unprotected.push("generation".to_string());
+    }
+    if !points_at_booted(gc_roots, &|_| true) {
+        unprotected.push("gc root".to_string());
+    }
+    Ok(unprotected)
+}
@@ -0,0 +1,103 @@
+// rather than growing an integration for everything, the widget can run the user's own commands
+// when something changes, handing them the status as JSON on stdin.
+
+use crate::error::{Error, IoContext, Result};
+use crate::state_dir::Stored;
+use crate::State;
+use std::io::Write;
+
+/// What the hooks last saw, so they only fire on a change.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Seen {
+    pub state: State,
+    pub reboot_needed: bool,
+    /// The lock date the widget was built with.
+    pub modified_date: i64,
+    /// Unix timestamps of when the state change and reboot hooks last fired, to remind from.
+    pub state_fired_at: Option<i64>,
+    pub reboot_fired_at: Option<i64>,
+}
+
+impl Stored for Seen {
+    const NAME: &'static str = "hooks.json";
+    const VERSION: u32 = 2;
+}
+
+/// Hours after which a hook fires again while what it's about persists, `None` for never. A
+/// Critical state or a pending reboot is easy to lose track of after the first notification.
+pub struct Renotify {
+    pub critical: Option<i64>,
+    pub reboot: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Hook {
+    StateChange,
+    RebootNeeded,
+    UpdateDetected,
+}
+
+/// The hooks going from `previous` to `now` at unix time `at` should fire, recording in `now` when
+/// they did. Nothing fires on the first run, since there's nothing to compare with, but reminders
+/// count from it.
+pub fn due(previous: Option<&Seen>, now: &mut Seen, at: i64, renotify: &Renotify) -> Vec<Hook> {
+    let Some(previous) = previous else {
+        now.state_fired_at = Some(at);
+        now.reboot_fired_at = Some(at);
+        return Vec::new();
+    };
+    now.state_fired_at = previous.state_fired_at;
+    now.reboot_fired_at = previous.reboot_fired_at;
+
+    let mut due = Vec::new();
+    if previous.state != now.state
+        || (now.state == State::Critical && reminder_due(now.state_fired_at, renotify.critical, at))
+    {
+        due.push(Hook::StateChange);
+        now.state_fired_at = Some(at);
+    }
+    if now.reboot_needed
+        && (!previous.reboot_needed || reminder_due(now.reboot_fired_at, renotify.reboot, at))
+    {
+        due.push(Hook::RebootNeeded);
+        now.reboot_fired_at = Some(at);
+    }
+    if previous.modified_date != now.modified_date {
+        due.push(Hook::UpdateDetected);
+    }
+    due
+}
+
+fn reminder_due(fired_at: Option<i64>, every_hours: Option<i64>, at: i64) -> bool {
+    match (fired_at, every_hours) {
+        (Some(fired_at), Some(hours)) => at - fired_at >= hours * 60 * 60,
+        (None, Some(_)) => true,
+        (_, None) => false,
+    }
+}
+
+/// Run `command` with `status` on its stdin and wait for it. Its stdout is where the bar reads
+/// from, so anything it prints there is dropped.
+pub fn run(command: &str, status: &str) -> Result<()> {
+    let mut child = std::process::Command::new(command)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .tool_context(|| format!("Could not run {command}"))?;
+    // a hook that doesn't read its stdin closes it early, which is fine
+    let _ = child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(status.as_bytes());
+    let status = child
+        .wait()
+        .tool_context(|| format!("Could not wait for {command}"))?;
+    if !status.success() {
+        return Err(Error::ExternalTool {
+            context: format!("{command} failed: {status}"),
+            source: None,
+        });
+    }
+    Ok(())
+}
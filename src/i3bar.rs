@@ -0,0 +1,105 @@
+// plain i3bar protocol blocks, for feeding i3bar directly or through an i3status wrapper
+// instead of going through i3status-rust's custom block.
+
+use crate::theme::Theme;
+use crate::{BarCommand, State};
+
+/// Identity and fixed layout hints for the block.
+#[derive(Default)]
+pub struct Layout {
+    /// Which block this is, so click events can be routed back to it.
+    pub name: Option<&'static str>,
+    /// Tells apart several copies of the same block.
+    pub instance: Option<&'static str>,
+    /// Text whose rendered width the block will be at least as wide as, e.g. "Age: 00".
+    pub min_width: Option<&'static str>,
+    /// "left", "center" or "right" within `min_width`.
+    pub align: Option<&'static str>,
+    pub separator_block_width: Option<u32>,
+}
+
+/// A block in the i3bar protocol.
+#[derive(serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub full_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator_block_width: Option<u32>,
+    pub urgent: bool,
+    /// Toggle `urgent` on every status line, for when a static red block is being ignored. Only
+    /// `--wrap` emits often enough for it to show.
+    #[serde(skip)]
+    pub blink: bool,
+}
+
+impl Block {
+    /// The theme colours the text and the border; the background is left to the bar, since a
+    /// coloured background needs a text colour picked to match it.
+    pub fn new(command: &BarCommand, layout: &Layout, theme: &Theme) -> Self {
+        let color = theme.color(command.state).map(str::to_string);
+        Block {
+            full_text: command.text.clone(),
+            name: layout.name.map(str::to_string),
+            instance: layout.instance.map(str::to_string),
+            border: color.clone(),
+            color,
+            background: None,
+            min_width: layout.min_width.map(str::to_string),
+            align: layout.align.map(str::to_string),
+            separator_block_width: layout.separator_block_width,
+            urgent: command.state == State::Critical,
+            blink: false,
+        }
+    }
+
+    /// Blink a Critical block once it's `overdue_days` past out of date and that's at least
+    /// `blink_after`, if set.
+    pub fn blinking(self, overdue_days: i64, blink_after: Option<i64>) -> Self {
+        Block {
+            blink: self.urgent && blink_after.is_some_and(|days| overdue_days >= days),
+            ..self
+        }
+    }
+
+    /// Ready the block for the next status line, where a blinking one has `urgent` the other way.
+    pub fn tick(&mut self) {
+        if self.blink {
+            self.urgent = !self.urgent;
+        }
+    }
+}
+
+/// A click event, as i3bar sends them on stdin when `click_events` is enabled.
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ClickEvent {
+    pub name: Option<String>,
+    pub instance: Option<String>,
+    pub button: u32,
+}
+
+impl ClickEvent {
+    /// Parse one line of the click event stream, which is an infinite JSON array so lines other
+    /// than the first start with a comma.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim().trim_start_matches(['[', ',']).trim();
+        serde_json::from_str(line).ok()
+    }
+
+    /// Whether the click was on this block rather than another one in the same bar.
+    pub fn is_for(&self, block: &Block) -> bool {
+        self.name == block.name && self.instance == block.instance
+    }
+}
@@ -0,0 +1,134 @@
+// the failed service check was written against systemd, but a few NixOS-like systems run runit or
+// s6 instead. each init is asked the same question its own way, and which one is running is told
+// from what it leaves in /run.
+
+use crate::error::{Error, IoContext, Result};
+use std::path::{Path, PathBuf};
+
+pub trait Init {
+    /// Services that are down while they're meant to be up, the user's own too if the init has them.
+    fn failed(&self) -> Result<Vec<String>>;
+    /// Whether asking means running a program, which no exec rules out.
+    fn runs_programs(&self) -> bool;
+}
+
+pub struct Systemd;
+
+impl Init for Systemd {
+    fn failed(&self) -> Result<Vec<String>> {
+        let mut failed = crate::failed_units::failed(false)?;
+        failed.extend(crate::failed_units::failed(true)?);
+        Ok(failed)
+    }
+
+    fn runs_programs(&self) -> bool {
+        true
+    }
+}
+
+/// runit, with its services linked into `dir` (`/var/service` or `/etc/service`).
+pub struct Runit {
+    pub dir: PathBuf,
+}
+
+impl Init for Runit {
+    fn failed(&self) -> Result<Vec<String>> {
+        Ok(services(&self.dir)?
+            .into_iter()
+            .filter(|(name, service)| {
+                // runsv keeps "run", "down" or "finish" there, with ", want down" when it was told to stop
+                let Ok(stat) = std::fs::read_to_string(service.join("supervise/stat")) else {
+                    return false;
+                };
+                let meant_up = !service.join("down").exists() && !stat.contains("want down");
+                !name.starts_with('.') && meant_up && !stat.starts_with("run")
+            })
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    fn runs_programs(&self) -> bool {
+        false
+    }
+}
+
+/// s6, supervising the services in its scan directory `dir`.
+pub struct S6 {
+    pub dir: PathBuf,
+}
+
+impl Init for S6 {
+    fn failed(&self) -> Result<Vec<String>> {
+        let mut failed = Vec::new();
+        for (name, service) in services(&self.dir)? {
+            // the scan directory has s6-svscan's own control directory in it
+            if name.starts_with('.') {
+                continue;
+            }
+            let output = std::process::Command::new("s6-svstat")
+                .args(["-o", "up,wantedup"])
+                .arg(&service)
+                .output()
+                .tool_context(|| "Could not run s6-svstat".to_string())?;
+            if !output.status.success() {
+                return Err(Error::ExternalTool {
+                    context: format!(
+                        "s6-svstat failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ),
+                    source: None,
+                });
+            }
+            if parse_svstat(&String::from_utf8_lossy(&output.stdout)) {
+                failed.push(name);
+            }
+        }
+        Ok(failed)
+    }
+
+    fn runs_programs(&self) -> bool {
+        true
+    }
+}
+
+/// Whether `s6-svstat -o up,wantedup` output says the service is down but wanted up.
+pub fn parse_svstat(output: &str) -> bool {
+    matches!(
+        output.split_whitespace().collect::<Vec<_>>()[..],
+        ["false", "true"]
+    )
+}
+
+/// The init running the system under `root` (normally `/`), if it's one of those known.
+pub fn detect(root: &Path) -> Option<Box<dyn Init>> {
+    // the same test as sd_booted()
+    if root.join("run/systemd/system").is_dir() {
+        return Some(Box::new(Systemd));
+    }
+    if root.join("run/runit").exists() {
+        let dir = ["var/service", "etc/service"]
+            .iter()
+            .map(|dir| root.join(dir))
+            .find(|dir| dir.is_dir())?;
+        return Some(Box::new(Runit { dir }));
+    }
+    if root.join("run/service/.s6-svscan").is_dir() {
+        return Some(Box::new(S6 {
+            dir: root.join("run/service"),
+        }));
+    }
+    None
+}
+
+/// The services in `dir` by name, sorted.
+fn services(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut services = std::fs::read_dir(dir)
+        .system_context(|| format!("Could not read {}", dir.display()))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            Some((entry.file_name().into_string().ok()?, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    services.sort();
+    Ok(services)
+}
@@ -0,0 +1,17 @@
+// a freshly provisioned machine comes up with whatever lock its installer image had, which can be
+// weeks old on purpose. systemd writes the machine id on first boot, so its age is the install's.
+
+use crate::error::{IoContext, Result};
+use std::path::Path;
+
+pub const MACHINE_ID: &str = "/etc/machine-id";
+
+/// Unix time `machine_id` was written, i.e. roughly when the system was first booted. `None` if
+/// there isn't one, e.g. in a container.
+pub fn installed_at(machine_id: &Path) -> Result<Option<i64>> {
+    match std::fs::metadata(machine_id) {
+        Ok(meta) => Ok(Some(std::os::unix::fs::MetadataExt::mtime(&meta))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).system_context(|| format!("Could not read {}", machine_id.display())),
+    }
+}
@@ -0,0 +1,72 @@
+// under systemd, stderr ends up in the journal anyway, but as lines of text. sending entries over
+// the journal's own socket instead keeps fields like the check and its duration apart, so
+// `journalctl -o json` can be filtered on them.
+
+use crate::Span;
+use std::path::Path;
+
+/// Where journald takes entries in its native protocol.
+pub const SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Whether `stderr` is the stream systemd connected to the journal, going by `$JOURNAL_STREAM`
+/// ("device:inode" of that stream), as sd_journal_stream_fd's documentation says to check.
+pub fn connected(journal_stream: Option<&str>, stderr: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Some((device, inode)) = journal_stream.and_then(|stream| stream.split_once(':')) else {
+        return false;
+    };
+    std::fs::metadata(stderr)
+        .is_ok_and(|meta| device.parse() == Ok(meta.dev()) && inode.parse() == Ok(meta.ino()))
+}
+
+/// `fields` as a journal entry. Values with a newline in them are sent with their length instead.
+pub fn entry(fields: &[(&str, String)]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    for (name, value) in fields {
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+    entry
+}
+
+/// The fields for one check run: debug priority if it went fine, a warning if it failed.
+pub fn span_fields(span: &Span) -> Vec<(&'static str, String)> {
+    let duration_ms = span.duration.as_millis();
+    let (priority, message, result) = match &span.error {
+        Some(error) => (
+            "4",
+            format!("{} check failed: {error}", span.check),
+            "error",
+        ),
+        None => (
+            "7",
+            format!("{} check took {duration_ms}ms", span.check),
+            "ok",
+        ),
+    };
+    vec![
+        ("MESSAGE", message),
+        ("PRIORITY", priority.to_string()),
+        (
+            "SYSLOG_IDENTIFIER",
+            "i3status-nix-update-widget".to_string(),
+        ),
+        ("CHECK", span.check.clone()),
+        ("DURATION_MS", duration_ms.to_string()),
+        ("RESULT", result.to_string()),
+    ]
+}
+
+/// Send `entry` to the journal listening on `socket`.
+pub fn send(socket: &Path, entry: &[u8]) -> std::io::Result<()> {
+    std::os::unix::net::UnixDatagram::unbound()?
+        .send_to(entry, socket)
+        .map(|_| ())
+}
@@ -0,0 +1,48 @@
+// on a server the kernel that's actually running can be far older than the lock says, if nobody
+// rebooted. nixpkgs builds kernels reproducibly, so /proc/version carries a 1980 date there and
+// the nix store has to be asked when the kernel arrived instead.
+
+use crate::error::{Error, IoContext, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// The build date at the end of `/proc/version`, e.g. "Tue May  7 10:15:35 UTC 2024". `None` if
+/// it's missing, or the reproducible build placeholder from 1980.
+pub fn build_date(proc_version: &str) -> Option<DateTime<Utc>> {
+    let words = proc_version.split_whitespace().collect::<Vec<_>>();
+    let date = words.get(words.len().checked_sub(6)?..)?.join(" ");
+    let date = NaiveDateTime::parse_from_str(&date, "%a %b %d %H:%M:%S UTC %Y")
+        .ok()?
+        .and_utc();
+    (date.timestamp() > 315_532_800).then_some(date)
+}
+
+/// When `nix path-info --json` says its one path was registered in the store. Understands both the
+/// list nix printed before 2.19 and the object keyed by path it prints since.
+pub fn registration_time(json: &str) -> Option<i64> {
+    let info: serde_json::Value = serde_json::from_str(json).ok()?;
+    let entry = match info {
+        serde_json::Value::Array(entries) => entries.into_iter().next()?,
+        serde_json::Value::Object(paths) => paths.into_iter().next()?.1,
+        _ => return None,
+    };
+    entry.get("registrationTime")?.as_i64()
+}
+
+/// When `path` was added to the store, by asking nix.
+pub fn registered(path: &str) -> Result<Option<i64>> {
+    let output = std::process::Command::new("nix")
+        .args(["--extra-experimental-features", "nix-command"])
+        .args(["path-info", "--json", path])
+        .output()
+        .tool_context(|| "Could not run nix path-info".to_string())?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "nix path-info failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(registration_time(&String::from_utf8_lossy(&output.stdout)))
+}
@@ -0,0 +1,645 @@
+// the logic lives here rather than in main so it can be tested without the constants
+// that get patched into modified_data.rs at build time
+
+pub mod agent;
+pub mod aggregate;
+pub mod boot;
+pub mod bug_report;
+pub mod deploy;
+pub mod doctor;
+pub mod error;
+pub mod etc_drift;
+pub mod failed_units;
+pub mod gc_roots;
+pub mod hooks;
+pub mod i3bar;
+pub mod init;
+pub mod install;
+pub mod journal;
+pub mod kernel_age;
+pub mod locale;
+pub mod lock_diff;
+pub mod maintenance;
+pub mod marker;
+pub mod metrics;
+pub mod modules;
+pub mod nix_version;
+pub mod otlp;
+pub mod processes;
+pub mod project;
+pub mod published;
+pub mod py3status;
+pub mod reboot;
+pub mod reboot_pending;
+pub mod registry;
+pub mod remote;
+pub mod rules;
+pub mod secureboot;
+pub mod session;
+pub mod state_dir;
+pub mod theme;
+pub mod toggles;
+pub mod waybar;
+pub mod wrap;
+
+use error::{Error, Result};
+
+/// Ordered by severity, so the worst of several is their `max()`.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum State {
+    Good,
+    Info,
+    Warning,
+    Critical,
+}
+
+/// The worst of `states` and what it came from, the first one of them on a tie.
+pub fn worst<'a>(states: impl IntoIterator<Item = (&'a str, State)>) -> Option<(&'a str, State)> {
+    states
+        .into_iter()
+        .fold(None, |worst, (cause, state)| match worst {
+            Some((_, worst_state)) if worst_state >= state => worst,
+            _ => Some((cause, state)),
+        })
+}
+
+/// How the state is written in the emitted block. Bars disagree on "critical" vs "Critical", and
+/// some want a level or a colour instead.
+pub enum StateNames {
+    /// "Good", "Critical" and so on, what i3status-rust expects.
+    Variant,
+    Lowercase,
+    /// 0 for Good up to 3 for Critical.
+    Numeric,
+    Custom {
+        info: &'static str,
+        good: &'static str,
+        warning: &'static str,
+        critical: &'static str,
+    },
+}
+
+impl State {
+    pub fn name(self, names: &StateNames) -> serde_json::Value {
+        let variant = match self {
+            State::Info => "Info",
+            State::Good => "Good",
+            State::Warning => "Warning",
+            State::Critical => "Critical",
+        };
+        match names {
+            StateNames::Variant => variant.into(),
+            StateNames::Lowercase => variant.to_lowercase().into(),
+            StateNames::Numeric => (self as u8).into(),
+            StateNames::Custom {
+                info,
+                good,
+                warning,
+                critical,
+            } => match self {
+                State::Info => *info,
+                State::Good => *good,
+                State::Warning => *warning,
+                State::Critical => *critical,
+            }
+            .into(),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct BarCommand {
+    pub icon: String,
+    pub state: State,
+    pub text: String,
+}
+
+/// Something one of the checks found that needs attention, like components needing a reboot.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// What kind of attention, e.g. "Reboot".
+    pub label: String,
+    pub items: Vec<String>,
+}
+
+impl Finding {
+    /// `label: a, b`, or just `label: N` once there are more than `inline_limit` items so a mass
+    /// rebuild doesn't take over the bar.
+    pub fn render(&self, inline_limit: usize) -> String {
+        if self.items.len() > inline_limit {
+            format!("{}: {}", self.label, self.items.len())
+        } else {
+            format!("{}: {}", self.label, self.items.join(", "))
+        }
+    }
+
+    /// One line per item, for the full picture.
+    pub fn render_details(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| format!("{}: {}\n", self.label, item))
+            .collect()
+    }
+}
+
+/// One run of one check, for telling when checks slow down or start failing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The check, by the name `disable` takes.
+    pub check: String,
+    /// Unix time in nanoseconds.
+    pub start: u128,
+    pub duration: std::time::Duration,
+    /// Why it failed, `None` if it didn't.
+    pub error: Option<String>,
+}
+
+/// Made up check results, e.g. for screenshots or trying out hooks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Simulation {
+    /// Age in days to use instead of the real one.
+    pub age: Option<i64>,
+    pub findings: Vec<Finding>,
+}
+
+impl Simulation {
+    /// Parse `age=20,reboot=kernel+initrd,boot-stale=nixos-generation-41.conf`. Other keys than
+    /// age are finding labels, with dashes for spaces, and `+` separates their items.
+    pub fn parse(spec: &str) -> Result<Simulation> {
+        let mut simulation = Simulation {
+            age: None,
+            findings: Vec::new(),
+        };
+        for pair in spec.split(',').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| Error::Config(format!("Simulated result {pair} needs a =")))?;
+            if key == "age" {
+                simulation.age =
+                    Some(value.parse().map_err(|_| {
+                        Error::Config(format!("Simulated age {value} isn't a number"))
+                    })?);
+                continue;
+            }
+            let mut label = key.replace('-', " ");
+            if let Some(first) = label.get_mut(..1) {
+                first.make_ascii_uppercase();
+            }
+            simulation.findings.push(Finding {
+                label,
+                items: value.split('+').map(str::to_string).collect(),
+            });
+        }
+        Ok(simulation)
+    }
+}
+
+/// Rename fields of `block` from the first name of each pair to the second, for consumers that
+/// expect other names. Fields it doesn't have are left alone.
+pub fn rename_fields(mut block: serde_json::Value, renames: &[(&str, &str)]) -> serde_json::Value {
+    if let Some(fields) = block.as_object_mut() {
+        for (from, to) in renames {
+            if let Some(value) = fields.remove(*from) {
+                fields.insert(to.to_string(), value);
+            }
+        }
+    }
+    block
+}
+
+/// Machine-readable reason for an age (of the lock or a tracked flake) in `state`, if it's one
+/// worth a reason.
+pub fn age_reason(state: State) -> Option<&'static str> {
+    match state {
+        State::Good | State::Info => None,
+        State::Warning => Some("AGE_OVER_WARNING"),
+        State::Critical => Some("AGE_OVER_CRITICAL"),
+    }
+}
+
+/// Machine-readable reasons for the whole block, from the `states` of the age and whatever else
+/// escalates like one, then the `findings`. Each only once, in the order they first come up.
+pub fn reasons(states: &[(String, State)], findings: &[Finding]) -> Vec<&'static str> {
+    let mut reasons = Vec::new();
+    let all = states
+        .iter()
+        .filter_map(|(cause, state)| match cause.as_str() {
+            // not an age, though it escalates like one
+            "reboot" => (*state >= State::Warning).then_some("REBOOT_OVERDUE"),
+            _ => age_reason(*state),
+        })
+        .chain(findings.iter().flat_map(finding_reasons));
+    for reason in all {
+        if !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+    reasons
+}
+
+/// Machine-readable reasons for `finding`, so automations don't have to match on its label.
+pub fn finding_reasons(finding: &Finding) -> Vec<&'static str> {
+    let reason = match finding.label.as_str() {
+        "Reboot" | "Reboot required" => {
+            return finding
+                .items
+                .iter()
+                .map(|item| match item.as_str() {
+                    "kernel" => "KERNEL_MISMATCH",
+                    "initrd" => "INITRD_MISMATCH",
+                    _ => "REBOOT_NEEDED",
+                })
+                .collect();
+        }
+        "Boot stale" => "BOOT_ENTRY_STALE",
+        "Unsigned" => "UNSIGNED_BOOT_FILE",
+        "Restart" => "SERVICE_RESTART_NEEDED",
+        "Unpaired" => "MODULE_MISMATCH",
+        "Units" => "UNIT_SET_CHANGED",
+        "Etc drift" => "ETC_DRIFT",
+        "Failed" => "UNIT_FAILED",
+        "Unprotected" => "BOOTED_SYSTEM_UNPROTECTED",
+        "Project" => "PROJECT_PIN_STALE",
+        "Registry" => "REGISTRY_PIN_STALE",
+        "Nix" => "NIX_TOO_OLD",
+        "Nix daemon" => "NIX_DAEMON_STALE",
+        _ => "OTHER",
+    };
+    vec![reason]
+}
+
+/// What it takes for everything pending to take effect, least disruptive first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    /// Log out and back in.
+    Relog,
+    /// Restart system services, or soft-reboot.
+    Restart,
+    Reboot,
+}
+
+impl Action {
+    pub fn advice(self) -> &'static str {
+        match self {
+            Action::Relog => "relog",
+            Action::Restart => "restart services",
+            Action::Reboot => "reboot",
+        }
+    }
+}
+
+/// The least disruptive action that's enough for all of `findings` to take effect. `session` is
+/// which of the processes needing a restart belong to the user's own session.
+pub fn action(findings: &[Finding], session: &[String]) -> Option<Action> {
+    findings
+        .iter()
+        .filter_map(|finding| match finding.label.as_str() {
+            "Reboot" | "Reboot required" | "Unpaired" => Some(Action::Reboot),
+            "Units" | "Nix daemon" => Some(Action::Restart),
+            "Restart" if finding.items.iter().all(|item| session.contains(item)) => {
+                Some(Action::Relog)
+            }
+            "Restart" => Some(Action::Restart),
+            _ => None,
+        })
+        .max()
+}
+
+/// The one thing to do next: update the lock if it's out of date (everything else would only have
+/// to be done again after), then switch to a generation that was built but not switched to, then
+/// whatever makes the rest take effect.
+pub fn recommendation(age: State, switch_pending: bool, action: Option<Action>) -> &'static str {
+    if age >= State::Warning {
+        "run flake update"
+    } else if switch_pending {
+        "switch pending config"
+    } else {
+        action.map_or("all good", Action::advice)
+    }
+}
+
+/// Add the fields of the JSON object `extra` to `block`, replacing any it already has.
+pub fn merge_fields(mut block: serde_json::Value, extra: &str) -> Result<serde_json::Value> {
+    let extra: serde_json::Map<String, serde_json::Value> = serde_json::from_str(extra)
+        .map_err(|_| Error::Config("Extra fields are not a JSON object".to_string()))?;
+
+    let fields = block
+        .as_object_mut()
+        .ok_or_else(|| Error::Config("Can only add fields to a JSON object".to_string()))?;
+    fields.extend(extra);
+
+    Ok(block)
+}
+
+/// Cut `text` down to at most `max_width` characters, ending in an ellipsis if anything was
+/// dropped. A `max_width` of 0 means no limit.
+pub fn truncate(text: &str, max_width: usize) -> String {
+    if max_width == 0 || text.chars().count() <= max_width {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(max_width - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// The output formats a block can be rendered in.
+pub const FORMATS: &[&str] = &["i3status-rs", "i3bar", "py3status", "waybar"];
+
+/// How this build writes its block, whichever format it's in.
+pub struct Style<'a> {
+    pub state_names: &'a StateNames,
+    pub i3bar_layout: &'a i3bar::Layout,
+    /// Colours py3status parts, and i3bar blocks only if `i3bar_colors` is set, so the bar's own
+    /// look is kept by default.
+    pub theme: &'a theme::Theme,
+    pub i3bar_colors: bool,
+    pub field_names: &'a [(&'a str, &'a str)],
+    /// A JSON object of fields to add.
+    pub extra_fields: &'a str,
+}
+
+/// What waybar shows besides the text: every finding in the tooltip, and the percentage.
+pub struct Tooltip<'a> {
+    pub findings: &'a [Finding],
+    /// What the last update changed, if it's recent enough to mention.
+    pub updated: Option<&'a str>,
+    pub percentage: u8,
+}
+
+impl Style<'_> {
+    /// The i3bar block for `code`.
+    pub fn i3bar_block(&self, code: &BarCommand) -> i3bar::Block {
+        let theme = if self.i3bar_colors {
+            self.theme
+        } else {
+            &theme::NONE
+        };
+        i3bar::Block::new(code, self.i3bar_layout, theme)
+    }
+
+    /// The block for `code` in `format`, with the fields renamed and the extra ones added.
+    pub fn render(
+        &self,
+        format: &str,
+        code: &BarCommand,
+        tooltip: &Tooltip,
+    ) -> Result<serde_json::Value> {
+        let mut block = match format {
+            "i3status-rs" => serde_json::to_value(code).map(|mut block| {
+                block["state"] = code.state.name(self.state_names);
+                block
+            }),
+            "i3bar" => serde_json::to_value(self.i3bar_block(code)),
+            "py3status" => serde_json::to_value(py3status::composite(code, self.theme)),
+            "waybar" => serde_json::to_value(waybar::Block::new(
+                code,
+                tooltip.findings,
+                tooltip.updated,
+                tooltip.percentage,
+            )),
+            other => return Err(Error::Config(format!("Unknown output format {other}"))),
+        }?;
+        // a composite is a list, so the extra fields go on its one part
+        let target = match block.as_array_mut() {
+            Some(parts) => parts
+                .first_mut()
+                .ok_or_else(|| Error::Config("Empty composite".to_string()))?,
+            None => &mut block,
+        };
+        *target = merge_fields(
+            rename_fields(target.take(), self.field_names),
+            self.extra_fields,
+        )?;
+        Ok(block)
+    }
+
+    /// A line for every state in every format, rendering what `sample` makes up for the state,
+    /// labelled by the state as the bar is told it.
+    pub fn preview<'t>(
+        &self,
+        sample: impl Fn(State) -> (BarCommand, Tooltip<'t>),
+    ) -> Result<Vec<String>> {
+        let mut lines = Vec::new();
+        for state in [State::Good, State::Info, State::Warning, State::Critical] {
+            let (code, tooltip) = sample(state);
+            let name = match state.name(self.state_names) {
+                serde_json::Value::String(name) => name,
+                name => name.to_string(),
+            };
+            for format in FORMATS {
+                lines.push(format!(
+                    "{format} {name}: {}",
+                    self.render(format, &code, &tooltip)?
+                ));
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// Source of the current time, so tests don't depend on when they are run.
+pub trait Clock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc>;
+}
+
+/// The real wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now()
+    }
+}
+
+/// A clock stuck at a single point in time.
+pub struct FixedClock(pub chrono::DateTime<chrono::Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        self.0
+    }
+}
+
+/// Ages in days at which the widget changes state.
+pub struct Thresholds {
+    pub good: i64,
+    pub update: i64,
+    pub out_of_date: i64,
+}
+
+/// Thresholds that apply on some days of the week between some hours, e.g. stricter ones on the
+/// weekend when there's actually time to update.
+pub struct Window {
+    pub weekdays: &'static [chrono::Weekday],
+    /// Hours of the day, `0..24` for all of it.
+    pub hours: std::ops::Range<u32>,
+    pub thresholds: Thresholds,
+}
+
+/// The thresholds of the first window in `schedule` that `time` falls into, or `default` if none
+/// of them match.
+pub fn thresholds_at<'a>(
+    time: &(impl chrono::Datelike + chrono::Timelike),
+    schedule: &'a [Window],
+    default: &'a Thresholds,
+) -> &'a Thresholds {
+    schedule
+        .iter()
+        .find(|window| {
+            window.weekdays.contains(&time.weekday()) && window.hours.contains(&time.hour())
+        })
+        .map_or(default, |window| &window.thresholds)
+}
+
+/// Another flake lock to keep an eye on next to the main one, e.g. a dotfiles or work flake.
+pub struct Tracked {
+    pub name: &'static str,
+    pub modified_date: i64,
+    pub thresholds: Thresholds,
+}
+
+/// Add each of `tracked` to the block: its age after `text`, and its state by its own thresholds
+/// to `states`, where the worst of them all decides the block. `days` is how old a flake's lock is.
+pub fn add_tracked<E>(
+    tracked: &[Tracked],
+    days: impl Fn(&Tracked) -> std::result::Result<i64, E>,
+    text: &mut String,
+    states: &mut Vec<(String, State)>,
+) -> std::result::Result<(), E> {
+    for tracked in tracked {
+        let days = days(tracked)?;
+        text.push_str(&format!(" {}: {days}", tracked.name));
+        states.push((
+            tracked.name.to_string(),
+            state_for_age(days, &tracked.thresholds),
+        ));
+    }
+    Ok(())
+}
+
+/// How long ago the lock was modified.
+pub struct Age {
+    /// Whole days since the lock was modified, never negative.
+    pub days: i64,
+    /// The lock claims to have been modified in the future, so one of the clocks involved is wrong.
+    pub clock_skew: bool,
+    /// When the lock was modified.
+    pub locked: chrono::DateTime<chrono::Utc>,
+}
+
+/// The i3status-rust block for `age` alone, as the full path renders it with default settings,
+/// written straight to `out` for builds that show nothing else. `icon` must need no JSON escaping.
+pub fn write_age_only(
+    out: &mut impl std::io::Write,
+    icon: &str,
+    age: &Age,
+    thresholds: &Thresholds,
+    skew_hint: bool,
+) -> std::io::Result<()> {
+    let skew = if age.clock_skew && skew_hint {
+        " (clock skew?)"
+    } else {
+        ""
+    };
+    writeln!(
+        out,
+        r#"{{"icon":"{icon}","state":"{:?}","text":"Age: {}{skew}"}}"#,
+        state_for_age(age.days, thresholds),
+        age.days,
+    )
+}
+
+/// Age of `modified_date` (a unix timestamp) as of now.
+pub fn lock_age(clock: &impl Clock, modified_date: i64) -> Result<Age> {
+    let time = chrono::DateTime::from_timestamp(modified_date, 0).ok_or_else(|| {
+        Error::FlakeLock("Could not deserialize timestamp. Corrupted flake?".to_string())
+    })?;
+
+    let since = clock.now().signed_duration_since(time);
+
+    Ok(Age {
+        days: since.num_days().max(0),
+        clock_skew: since < chrono::Duration::zero(),
+        locked: time,
+    })
+}
+
+/// How long until the next threshold is crossed, e.g. "update within 3d".
+pub fn countdown(duration_days: i64, thresholds: &Thresholds) -> String {
+    if duration_days < thresholds.update {
+        format!("update within {}d", thresholds.update - duration_days)
+    } else if duration_days < thresholds.out_of_date {
+        format!("out of date in {}d", thresholds.out_of_date - duration_days)
+    } else {
+        "out of date".to_string()
+    }
+}
+
+/// Calendar days between `locked` and `now`, counting midnights in their timezone rather than
+/// whole 24 hour periods.
+pub fn calendar_days<Tz: chrono::TimeZone>(
+    now: &chrono::DateTime<Tz>,
+    locked: &chrono::DateTime<Tz>,
+) -> i64 {
+    (now.date_naive() - locked.date_naive()).num_days().max(0)
+}
+
+/// A configured timezone other than "local": "UTC" or a fixed offset like "+05:30".
+pub fn parse_timezone(timezone: &str) -> Result<chrono::FixedOffset> {
+    if timezone == "UTC" {
+        return Ok(chrono::FixedOffset::east_opt(0).expect("zero is a valid offset"));
+    }
+    timezone
+        .parse()
+        .map_err(|_| Error::Config(format!("Unknown timezone {timezone}")))
+}
+
+/// The lock's date as shown in the bar, "locked 2024-05-02", or "locked 2024-W18" for the ISO week.
+pub fn lock_date<Tz: chrono::TimeZone>(locked: &chrono::DateTime<Tz>, week: bool) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if week {
+        format!("locked {}", locked.format("%G-W%V"))
+    } else {
+        format!("locked {}", locked.format("%Y-%m-%d"))
+    }
+}
+
+/// Time since boot, parsed from the contents of `/proc/uptime`.
+pub fn parse_uptime(proc_uptime: &str) -> Result<chrono::Duration> {
+    let seconds: f64 = proc_uptime
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse().ok())
+        .ok_or_else(|| Error::SystemProfile {
+            context: "Could not parse /proc/uptime".to_string(),
+            source: None,
+        })?;
+
+    Ok(chrono::Duration::seconds(seconds as i64))
+}
+
+/// Map an age in days onto a bar state.
+///
+/// Thresholds are checked from most to least severe, so when two of them are equal the more
+/// severe state wins. Ages strictly between `good` and `update` are `Info`, and negative ages
+/// (a lock modified in the future) count as `Good`.
+pub fn state_for_age(duration_days: i64, thresholds: &Thresholds) -> State {
+    if duration_days >= thresholds.out_of_date {
+        // it is critical that you update
+        State::Critical
+    } else if duration_days >= thresholds.update {
+        // warn to update
+        State::Warning
+    } else if duration_days <= thresholds.good {
+        // you don't need to update yet
+        State::Good
+    } else {
+        // getting on a bit, but not worth a warning yet
+        State::Info
+    }
+}
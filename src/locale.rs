@@ -0,0 +1,44 @@
+// the age can be shown as a phrase in the user's language instead of "Age: N". there are only a
+// handful of strings involved, so a small table does the job without pulling in an i18n crate.
+
+/// Language of a locale like `de_DE.UTF-8`, or `None` for the "C"/"POSIX" locales.
+pub fn language(locale: &str) -> Option<&str> {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    match language {
+        "" | "C" | "POSIX" => None,
+        language => Some(language),
+    }
+}
+
+/// Language to use for `configured`, with "auto" going by the usual locale variables.
+pub fn select(configured: &str, var: impl Fn(&str) -> Option<String>) -> String {
+    if configured != "auto" {
+        return configured.to_string();
+    }
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|name| var(name).filter(|value| !value.is_empty()))
+        .find_map(|locale| language(&locale).map(str::to_string))
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// How long ago the lock is, e.g. "vor 3 Tagen". Languages without a table fall back to English.
+pub fn relative_age(days: i64, language: &str) -> String {
+    match (language, days) {
+        ("de", 0) => "heute".to_string(),
+        ("de", 1) => "vor 1 Tag".to_string(),
+        ("de", days) => format!("vor {days} Tagen"),
+        ("fr", 0) => "aujourd'hui".to_string(),
+        ("fr", 1) => "il y a 1 jour".to_string(),
+        ("fr", days) => format!("il y a {days} jours"),
+        ("es", 0) => "hoy".to_string(),
+        ("es", 1) => "hace 1 día".to_string(),
+        ("es", days) => format!("hace {days} días"),
+        ("nl", 0) => "vandaag".to_string(),
+        ("nl", 1) => "1 dag geleden".to_string(),
+        ("nl", days) => format!("{days} dagen geleden"),
+        (_, 0) => "today".to_string(),
+        (_, 1) => "1 day ago".to_string(),
+        (_, days) => format!("{days} days ago"),
+    }
+}
@@ -0,0 +1,92 @@
+// the lock dates are baked in at build time, so a rebuild with a newer lock is what an update looks
+// like from here. remembering the previous dates lets us say what the update actually moved.
+
+use crate::state_dir::{StateDir, Stored};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LockHistory {
+    /// Every input with a date, as of the last run.
+    pub inputs: Vec<(String, i64)>,
+    /// Unix timestamp of when the inputs were last seen to change.
+    pub changed_at: Option<i64>,
+    /// How the inputs moved in that change, e.g. "nixpkgs +9d".
+    pub moved: Vec<String>,
+}
+
+impl Stored for LockHistory {
+    const NAME: &'static str = "lock_history.json";
+    const VERSION: u32 = 1;
+}
+
+/// How each input moved from `before` to `after`, in days between their dates.
+pub fn moved(before: &[(String, i64)], after: &[(&str, i64)]) -> Vec<String> {
+    let mut moved = Vec::new();
+    for (input, date) in after {
+        match before.iter().find(|(name, _)| name == input) {
+            Some((_, previous)) if previous == date => {}
+            Some((_, previous)) => {
+                let days = (date - previous) / (24 * 60 * 60);
+                moved.push(format!("{input} {days:+}d"));
+            }
+            None => moved.push(format!("{input} new")),
+        }
+    }
+    for (input, _) in before {
+        if !after.iter().any(|(name, _)| name == input) {
+            moved.push(format!("{input} removed"));
+        }
+    }
+    moved
+}
+
+/// The history once `inputs` have been seen at unix time `now`. The first run has nothing to compare
+/// against, so it only records them.
+pub fn advance(previous: Option<LockHistory>, inputs: &[(&str, i64)], now: i64) -> LockHistory {
+    let current = inputs
+        .iter()
+        .map(|(input, date)| (input.to_string(), *date))
+        .collect::<Vec<_>>();
+    match previous {
+        Some(previous) if previous.inputs == current => previous,
+        Some(previous) => LockHistory {
+            moved: moved(&previous.inputs, inputs),
+            inputs: current,
+            changed_at: Some(now),
+        },
+        None => LockHistory {
+            inputs: current,
+            changed_at: None,
+            moved: Vec::new(),
+        },
+    }
+}
+
+/// [`advance`] the history kept in `state_dir`, saving it if it moved. Not being able to save it (on
+/// a read-only or stateless system) goes into `notes` and the run carries on with it unsaved.
+pub fn record(
+    state_dir: &StateDir,
+    inputs: &[(&str, i64)],
+    now: i64,
+    notes: &mut Vec<String>,
+) -> LockHistory {
+    let previous = state_dir.read::<LockHistory>();
+    let history = advance(previous.clone(), inputs, now);
+    if previous.as_ref() != Some(&history) {
+        if let Err(err) = state_dir.write(&history) {
+            notes.push(format!("lock history not saved: {err:#}"));
+        }
+    }
+    history
+}
+
+/// The line `summary` ends with: what moved if the last change was within the week before unix time
+/// `now`, otherwise that nothing did.
+pub fn this_week(history: Option<&LockHistory>, now: i64) -> String {
+    let week_ago = now - 7 * 24 * 60 * 60;
+    match history {
+        Some(history) if history.changed_at.is_some_and(|at| at >= week_ago) => {
+            format!("Updated this week: {}", history.moved.join(", "))
+        }
+        _ => "No updates this week".to_string(),
+    }
+}
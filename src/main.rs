@@ -6,56 +6,1386 @@
 
 include!("modified_data.rs");
 
-#[derive(serde::Serialize)]
-pub enum State {
-    Info,
-    Good,
-    Warning,
-    Critical,
-}
-
-#[derive(serde::Serialize)]
-pub struct BarCommand {
-    icon: String,
-    state: State,
+use anyhow::Context;
+use i3status_nix_update_widget::{
+    action, add_tracked, agent, aggregate, boot, bug_report, calendar_days, countdown, deploy,
+    doctor, etc_drift, gc_roots, hooks, i3bar, init, install, journal, kernel_age, locale,
+    lock_age, lock_date, lock_diff, maintenance, marker, metrics, modules, nix_version, otlp,
+    parse_timezone, parse_uptime, processes, project, published::Published, reasons, reboot,
+    reboot::Probe, reboot_pending, recommendation, registry, remote, rules, rules::Rule,
+    secureboot, session, state_dir, state_dir::StateDir, state_for_age, theme, thresholds_at,
+    toggles, truncate, waybar, worst, wrap, write_age_only, Action, BarCommand, Clock, Finding,
+    Simulation, Span, State, StateNames, Style, SystemClock, Thresholds, Tooltip, Tracked, Window,
+};
+
+const THRESHOLDS: Thresholds = Thresholds {
+    good: GOOD_THRESHOLD,
+    update: UPDATE_THRESHOLD,
+    out_of_date: OUT_OF_DATE_THRESHOLD,
+};
+
+const I3BAR_LAYOUT: i3bar::Layout = i3bar::Layout {
+    name: I3BAR_NAME,
+    instance: I3BAR_INSTANCE,
+    min_width: I3BAR_MIN_WIDTH,
+    align: I3BAR_ALIGN,
+    separator_block_width: I3BAR_SEPARATOR_BLOCK_WIDTH,
+};
+
+/// What all the enabled checks came up with.
+struct Report {
+    age_days: i64,
+    /// How far the age is towards the out of date threshold in effect.
+    percentage: u8,
+    /// Days since the age went past that threshold, negative before it does.
+    overdue_days: i64,
+    /// What the last update moved, while it's recent.
+    updated: Option<String>,
+    /// What to do about the findings, when that's asked for.
+    action: Option<Action>,
+    /// The one thing to do next, e.g. "run flake update" or "all good".
+    recommendation: &'static str,
+    /// The state of the age and each tracked flake, by what it's for.
+    states: Vec<(String, State)>,
     text: String,
+    findings: Vec<Finding>,
+    /// What went wrong without being worth failing over, like state that couldn't be saved.
+    notes: Vec<String>,
 }
 
-use anyhow::Context;
+/// `no_exec` keeps every check to reading files, never spawning anything. A `simulation` stands in
+/// for what the checks would have found. `export` sends their timings to the OpenTelemetry
+/// collector in the background, which only a run that goes on afterwards, like `--wrap`, should ask
+/// for: a one-off run would either hold up the bar waiting for it or drop it on exit.
+fn report(no_exec: bool, simulation: Option<&Simulation>, export: bool) -> anyhow::Result<Report> {
+    let mut spans = Vec::new();
+    let report = run_checks(no_exec, simulation, &mut spans);
+    log_spans(&spans);
+    if export {
+        export_spans(&spans);
+    }
+    report
+}
 
-fn main() -> anyhow::Result<()> {
-    let now = chrono::Utc::now();
+fn run_checks(
+    no_exec: bool,
+    simulation: Option<&Simulation>,
+    spans: &mut Vec<Span>,
+) -> anyhow::Result<Report> {
+    let mut age = lock_age(&SystemClock, MODIFIED_DATE)?;
+    let now = in_timezone(SystemClock.now())?;
+    if CALENDAR_DAYS {
+        age.days = calendar_days(&now, &in_timezone(age.locked)?);
+    }
+    if let Some(days) = simulation.and_then(|simulation| simulation.age) {
+        age.days = days;
+    }
+
+    let thresholds = thresholds_at(&now, THRESHOLD_SCHEDULE, &THRESHOLDS);
+    let mut age_state = state_for_age(age.days, thresholds);
+    if let Some(path) = MAINTENANCE_CALENDAR {
+        let ics = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read maintenance calendar {path}"))?;
+        let windows = maintenance::parse_ics(&ics, now.offset());
+        age_state = match maintenance::planned(&windows, SystemClock.now(), age.locked) {
+            maintenance::Planned::During => age_state.min(State::Info),
+            maintenance::Planned::Missed => State::Critical,
+            maintenance::Planned::Nothing => age_state,
+        };
+    }
+    if let Some(days) = INSTALL_GRACE_DAYS {
+        if let Some(installed) = install::installed_at(std::path::Path::new(install::MACHINE_ID))? {
+            if lock_age(&SystemClock, installed)?.days < days {
+                age_state = age_state.min(State::Info);
+            }
+        }
+    }
+    let mut states = vec![("age".to_string(), age_state)];
+
+    let deployed_at = match DEPLOY_MARKER {
+        Some(marker) => deploy::deployed_at(std::path::Path::new(marker))?,
+        None => None,
+    };
+    // a deploy marker existing is as good as saying which tool does the deploying
+    let deployed = DEPLOYED_BY.is_some() || deployed_at.is_some();
+
+    let mut text = AGE_DISPLAY
+        .iter()
+        .map(|part| match *part {
+            "age" => Ok(match AGE_LANGUAGE {
+                Some(language) => locale::relative_age(
+                    age.days,
+                    &locale::select(language, |name| std::env::var(name).ok()),
+                ),
+                None => format!("Age: {}", age.days),
+            }),
+            "countdown" if deployed => {
+                Ok(countdown(age.days, thresholds).replacen("update", "deploy", 1))
+            }
+            "countdown" => Ok(countdown(age.days, thresholds)),
+            "date" | "week" => Ok(lock_date(&in_timezone(age.locked)?, *part == "week")),
+            other => Err(anyhow::anyhow!("Unknown age display {other}")),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .join(" ");
+    if age.clock_skew && SHOW_CLOCK_SKEW_HINT {
+        text.push_str(" (clock skew?)");
+    }
+    if deployed && age_state >= State::Warning {
+        text.push(' ');
+        text.push_str(&deploy::awaiting(DEPLOYED_BY));
+    }
+    if let Some(deployed_at) = deployed_at {
+        text.push_str(&format!(
+            " Deployed: {}",
+            lock_age(&SystemClock, deployed_at)?.days
+        ));
+    }
+
+    add_tracked(
+        TRACKED_FLAKES,
+        |tracked| {
+            let age = lock_age(&SystemClock, tracked.modified_date)?;
+            anyhow::Ok(if CALENDAR_DAYS {
+                calendar_days(&now, &in_timezone(age.locked)?)
+            } else {
+                age.days
+            })
+        },
+        &mut text,
+        &mut states,
+    )?;
+
+    if SHOW_UPTIME {
+        let proc_uptime =
+            std::fs::read_to_string("/proc/uptime").context("Could not read /proc/uptime")?;
+        text.push_str(&format!(" Up: {}", parse_uptime(&proc_uptime)?.num_days()));
+    }
+
+    if SHOW_KERNEL_AGE {
+        if let Some(built) = kernel_built(no_exec)? {
+            text.push_str(&format!(" Kernel: {}", lock_age(&SystemClock, built)?.days));
+        }
+    }
+
+    // read once, for skipping the checks that are off and for saying which they are
+    let disabled = disabled_checks();
+    let mut notes = disabled
+        .off(SystemClock.now().timestamp())
+        .into_iter()
+        .map(|check| format!("{check} check disabled"))
+        .collect::<Vec<_>>();
+    let findings = match simulation {
+        Some(simulation) => simulation.findings.clone(),
+        None => findings(no_exec, &disabled, spans, &mut notes)?,
+    };
+    let findings = rules::at_least(rules::apply(findings, ITEM_RULES), ITEM_RULES, MIN_SEVERITY);
+
+    let session = if CHECK_DELETED_STORE_PATHS && simulation.is_none() {
+        let uid = std::os::unix::fs::MetadataExt::uid(
+            &std::fs::metadata("/proc/self").context("Could not read /proc/self")?,
+        );
+        processes::owned_processes_using_deleted_store_paths(std::path::Path::new("/proc"), uid)?
+    } else {
+        vec![]
+    };
+    let needed = action(&findings, &session);
+    // the system profile moves on when a generation is built, /run/current-system once it's switched to
+    let profile = std::fs::canonicalize(boot::SYSTEM_PROFILE).ok();
+    let switch_pending =
+        profile.is_some() && profile != std::fs::canonicalize(reboot::CURRENT_SYSTEM).ok();
+    let recommendation = recommendation(states[0].1, switch_pending, needed);
+    if RECOMMENDATION_TEXT {
+        text = recommendation.to_string();
+    }
+
+    let updated = match SHOW_UPDATE_DAYS {
+        Some(days) => recent_update(days, &mut notes),
+        None => None,
+    };
+
+    if let Some(reboot_thresholds) = &REBOOT_THRESHOLDS {
+        let needed = findings
+            .iter()
+            .any(|finding| finding.label.starts_with("Reboot"));
+        if let Some(days) = reboot_pending_days(needed, &mut notes) {
+            text.push_str(&format!(" reboot pending for {days}d"));
+            states.push(("reboot".to_string(), state_for_age(days, reboot_thresholds)));
+        }
+    }
+
+    Ok(Report {
+        age_days: age.days,
+        updated,
+        action: needed.filter(|_| SHOW_ACTION),
+        recommendation,
+        percentage: waybar::percentage(age.days, thresholds.out_of_date),
+        overdue_days: age.days - thresholds.out_of_date,
+        states,
+        text,
+        findings,
+        notes,
+    })
+}
+
+/// Every check besides the age, by the name `disable` takes, and whether this build runs it. The
+/// age-only fast path goes by this too, so a check missing here never runs at all.
+const CHECKS: &[(&str, bool)] = &[
+    ("boot-stale", CHECK_BOOT_FILES),
+    ("unsigned", CHECK_SECURE_BOOT),
+    ("restart", CHECK_DELETED_STORE_PATHS),
+    ("nix-daemon", CHECK_NIX_DAEMON),
+    ("reboot", CHECK_REBOOT),
+    ("units", CHECK_UNITS),
+    ("etc-drift", !ETC_PATHS.is_empty()),
+    ("failed", CHECK_FAILED_UNITS),
+    ("reboot-required", READ_REBOOT_REQUIRED),
+    ("unprotected", CHECK_GC_ROOTS),
+    ("unpaired", !PAIRED_MODULES.is_empty()),
+    ("project", PROJECT_THRESHOLD.is_some()),
+    ("registry", REGISTRY_THRESHOLD.is_some()),
+    ("nix", NIX_VERSION_FLOOR.is_some()),
+];
+
+/// Whether `check` is built in and not switched off with `disable`.
+fn runs(check: &str, disabled: &toggles::Disabled, now: i64) -> bool {
+    CHECKS.iter().any(|(name, built)| *name == check && *built) && !disabled.is_off(check, now)
+}
+
+/// What all the enabled checks besides the age found, with how long each took going into `spans`.
+/// Not being able to keep the reboot-required markers in sync goes into `notes`.
+fn findings(
+    no_exec: bool,
+    disabled: &toggles::Disabled,
+    spans: &mut Vec<Span>,
+    notes: &mut Vec<String>,
+) -> anyhow::Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    let now = SystemClock.now().timestamp();
+    let on = |check| runs(check, disabled, now);
+
+    match PRIVILEGED_CHECKS {
+        "local" => findings.extend(privileged_findings(no_exec, disabled, spans)?),
+        "published" => {
+            let published = system_state_dir().read::<Published>().context(
+                "No published results from the privileged checks, is the service running?",
+            )?;
+            // kept, they're the best there is, but not passed off as current
+            findings.extend(published.stale(now, PUBLISHED_MAX_HOURS));
+            findings.extend(published.findings);
+        }
+        other => anyhow::bail!("Unknown privileged checks mode {other}"),
+    }
+
+    if on("reboot") {
+        let changed = timed(spans, "reboot", || {
+            let changed = match REBOOT_BACKEND {
+                "builtin" => match system_state_dir().read::<marker::Marker>() {
+                    Some(marker)
+                        if marker.is_current(
+                            std::path::Path::new(reboot::CURRENT_SYSTEM),
+                            booted_at()?,
+                        ) =>
+                    {
+                        marker.changed
+                    }
+                    _ => changed_since_boot(no_exec)?,
+                },
+                // it writes its verdict to /var/run/reboot-required from the activation script
+                "nixos-needsreboot" => {
+                    reboot::read_reboot_required(std::path::Path::new(reboot::REBOOT_REQUIRED_DIR))?
+                }
+                other => anyhow::bail!("Unknown reboot check backend {other}"),
+            };
+            // don't fight nixos-needsreboot over its own marker
+            if WRITE_REBOOT_REQUIRED && REBOOT_BACKEND == "builtin" && !stateless() {
+                if let Err(err) = reboot::sync_reboot_required(
+                    std::path::Path::new(reboot::REBOOT_REQUIRED_DIR),
+                    &changed,
+                ) {
+                    notes.push(format!("reboot-required not updated: {err:#}"));
+                }
+            }
+            Ok(changed)
+        })?;
+        found(&mut findings, "Reboot", changed);
+    }
+
+    if on("units") {
+        let units = timed(spans, "units", || {
+            Ok(reboot::changed_units(
+                std::path::Path::new(reboot::BOOTED_SYSTEM),
+                std::path::Path::new(reboot::CURRENT_SYSTEM),
+            )?)
+        })?;
+        found(&mut findings, "Units", units);
+    }
+
+    if on("etc-drift") {
+        let drifted = timed(spans, "etc-drift", || {
+            Ok(etc_drift::drifted(
+                std::path::Path::new(etc_drift::LIVE_ETC),
+                &std::path::Path::new(reboot::CURRENT_SYSTEM).join("etc"),
+                ETC_PATHS,
+            ))
+        })?;
+        found(&mut findings, "Etc drift", drifted);
+    }
+
+    if on("failed") {
+        // an init that isn't known to the widget has nothing to say
+        if let Some(init) = init::detect(std::path::Path::new("/")) {
+            if !no_exec || !init.runs_programs() {
+                let failed = timed(spans, "failed", || Ok(init.failed()?))?;
+                found(&mut findings, "Failed", failed);
+            }
+        }
+    }
+
+    if on("reboot-required") {
+        let required = timed(spans, "reboot-required", || {
+            Ok(reboot::read_reboot_required(std::path::Path::new(
+                reboot::REBOOT_REQUIRED_DIR,
+            ))?)
+        })?;
+        found(&mut findings, "Reboot required", required);
+    }
+
+    let reboot_pending = findings
+        .iter()
+        .any(|finding| finding.label.starts_with("Reboot"));
+    if reboot_pending && on("unprotected") {
+        let unprotected = timed(spans, "unprotected", || {
+            Ok(gc_roots::unprotected(
+                std::path::Path::new(reboot::BOOTED_SYSTEM),
+                std::path::Path::new(boot::SYSTEM_PROFILE),
+                std::path::Path::new(gc_roots::GC_ROOTS),
+            )?)
+        })?;
+        found(&mut findings, "Unprotected", unprotected);
+    }
+
+    if on("unpaired") {
+        let unpaired = timed(spans, "unpaired", || {
+            Ok(modules::mismatched_modules(
+                std::path::Path::new(boot::SYSTEM_PROFILE),
+                PAIRED_MODULES,
+            )?)
+        })?;
+        found(&mut findings, "Unpaired", unpaired);
+    }
+
+    if let Some(threshold) = PROJECT_THRESHOLD.filter(|_| on("project")) {
+        let pinned = timed(spans, "project", || {
+            let dir = project::project_dir(
+                std::env::var("DIRENV_DIR").ok().as_deref(),
+                &std::env::current_dir().context("Could not get the working directory")?,
+            );
+            Ok(project::pinned_nixpkgs(&dir)?)
+        })?;
+        if let Some(pinned) = pinned {
+            let days = lock_age(&SystemClock, pinned)?.days;
+            if days > threshold {
+                found(&mut findings, "Project", vec![format!("nixpkgs {days}d")]);
+            }
+        }
+    }
 
-    let time = chrono::DateTime::from_timestamp(MODIFIED_DATE, 0)
-        .context("Could not deserialize timestamp. Corrupted flake?")?;
+    if let Some(threshold) = REGISTRY_THRESHOLD.filter(|_| on("registry")) {
+        let stale = timed(spans, "registry", || {
+            let registries = registry::user_registry()
+                .into_iter()
+                .chain([std::path::PathBuf::from(registry::SYSTEM_REGISTRY)]);
+            let mut stale = Vec::new();
+            for path in registries {
+                for (id, pinned) in registry::pins(&path)? {
+                    let days = lock_age(&SystemClock, pinned)?.days;
+                    if days > threshold {
+                        stale.push(format!("{id} {days}d"));
+                    }
+                }
+            }
+            Ok(stale)
+        })?;
+        found(&mut findings, "Registry", stale);
+    }
+
+    if let Some(floor) = NIX_VERSION_FLOOR.filter(|_| on("nix")) {
+        let installed = timed(spans, "nix", || {
+            Ok(nix_version::installed(std::path::Path::new(
+                reboot::CURRENT_SYSTEM,
+            ))?)
+        })?;
+        if let Some(version) = nix_version::version_of(&installed) {
+            if nix_version::older_than(&version, floor) {
+                found(&mut findings, "Nix", vec![format!("{version} < {floor}")]);
+            }
+        }
+    }
+
+    Ok(findings)
+}
 
-    let duration_days = now.signed_duration_since(time).num_days();
+/// "updated today: nixpkgs +9d" if the lock changed in the last `days` days. Not being able to
+/// save the history (on a read-only or stateless system) goes into `notes`.
+fn recent_update(days: i64, notes: &mut Vec<String>) -> Option<String> {
+    let state_dir = user_state_dir()?;
+    let now = SystemClock.now().timestamp();
+    let history = lock_diff::record(&state_dir, INPUT_DATES, now, notes);
 
-    let status: State;
+    history.changed_at.and_then(|changed_at| {
+        let ago = (now - changed_at) / (24 * 60 * 60);
+        (ago < days).then(|| {
+            format!(
+                "updated {}: {}",
+                locale::relative_age(ago, "en"),
+                history.moved.join(", ")
+            )
+        })
+    })
+}
 
-    if duration_days >= OUT_OF_DATE_THRESHOLD {
-        // it is critical that you update
-        status = State::Critical;
-    } else if duration_days >= UPDATE_THRESHOLD {
-        // warn to update
-        status = State::Warning;
-    } else if duration_days <= GOOD_THRESHOLD {
-        // you don't need to update yet
-        status = State::Good;
+/// For how many days a reboot has been needed, if it is. Not being able to remember since when
+/// goes into `notes`, and then it's counted from now.
+fn reboot_pending_days(needed: bool, notes: &mut Vec<String>) -> Option<i64> {
+    let now = SystemClock.now().timestamp();
+    let state_dir = user_state_dir();
+    let previous = state_dir
+        .as_ref()
+        .and_then(|dir| dir.read::<reboot_pending::RebootPending>());
+    let pending = reboot_pending::advance(previous.as_ref(), needed, now);
+    if previous.as_ref() != Some(&pending) {
+        if let Some(Err(err)) = state_dir.map(|dir| dir.write(&pending)) {
+            notes.push(format!("pending reboot not saved: {err:#}"));
+        }
+    }
+    pending.since.map(|since| (now - since) / (24 * 60 * 60))
+}
+
+/// What needs a reboot to take effect: the kernel, initrd, paired modules and whatever the probes
+/// compare.
+fn changed_since_boot(no_exec: bool) -> anyhow::Result<Vec<String>> {
+    let booted = std::path::Path::new(reboot::BOOTED_SYSTEM);
+    let current = std::path::Path::new(reboot::CURRENT_SYSTEM);
+    let mut changed = reboot::changed_boot_components(booted, current)?;
+    changed.extend(reboot::changed_probes(
+        REBOOT_PROBES,
+        booted,
+        current,
+        no_exec,
+    )?);
+    let modules = modules::changed_modules(
+        booted,
+        current,
+        PAIRED_MODULES,
+        (!no_exec).then_some(MODINFO),
+    )?;
+    changed.extend(modules.versioned);
+    if GENERIC_MODULE_CHANGES && !modules.unversioned.is_empty() {
+        changed.push("module changed".to_string());
     } else {
-        unreachable!("all possible values of duration_days are handled");
+        changed.extend(modules.unversioned);
+    }
+    Ok(changed)
+}
+
+/// The checks that need root to see everything: the ESP, sbctl and other users' processes.
+fn privileged_findings(
+    no_exec: bool,
+    disabled: &toggles::Disabled,
+    spans: &mut Vec<Span>,
+) -> anyhow::Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    let now = SystemClock.now().timestamp();
+    let on = |check| runs(check, disabled, now);
+
+    if on("boot-stale") {
+        let stale = timed(spans, "boot-stale", || {
+            Ok(boot::stale_boot_files(
+                std::path::Path::new(boot::BOOT),
+                std::path::Path::new(boot::SYSTEM_PROFILE),
+            )?)
+        })?;
+        found(&mut findings, "Boot stale", stale);
+    }
+
+    if on("unsigned") {
+        let unsigned = timed(spans, "unsigned", || {
+            Ok(secureboot::unsigned_files(
+                (!no_exec).then_some(SBCTL),
+                std::path::Path::new(secureboot::ESP_EFI_DIR),
+            )?)
+        })?;
+        found(
+            &mut findings,
+            "Unsigned",
+            unsigned
+                .iter()
+                .map(|file| file.rsplit('/').next().unwrap_or(file).to_string())
+                .collect(),
+        );
+    }
+
+    if on("restart") {
+        let processes = timed(spans, "restart", || {
+            Ok(processes::processes_using_deleted_store_paths(
+                std::path::Path::new("/proc"),
+            )?)
+        })?;
+        found(&mut findings, "Restart", processes);
+    }
+
+    if on("nix-daemon") {
+        let (installed, daemon) = timed(spans, "nix-daemon", || {
+            Ok((
+                nix_version::installed(std::path::Path::new(reboot::CURRENT_SYSTEM))?,
+                nix_version::daemon_binary(std::path::Path::new("/proc"))?,
+            ))
+        })?;
+        if let Some(daemon) = daemon {
+            // same package means same binary, even if it's a different file in it
+            if daemon.parent() != installed.parent() {
+                let version = nix_version::version_of(&daemon).unwrap_or_default();
+                found(
+                    &mut findings,
+                    "Nix daemon",
+                    vec![format!("{version}, systemctl restart nix-daemon")],
+                );
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// The report as `--details --json`.
+fn details_json(report: anyhow::Result<Report>) -> serde_json::Value {
+    match report {
+        Ok(report) => {
+            let (cause, state) = overall(&report);
+            serde_json::json!({
+                "state": state,
+                "cause": cause,
+                "reasons": reasons(&report.states, &report.findings),
+                "text": report.text,
+                "percentage": report.percentage,
+                "updated": report.updated,
+                "findings": report.findings,
+                "recommendation": report.recommendation,
+                "notes": report.notes,
+            })
+        }
+        // automations still get an answer they can branch on
+        Err(err) => serde_json::json!({
+            "state": State::Critical,
+            "cause": "error",
+            "reasons": ["CHECK_FAILED"],
+            "text": format!("{err:#}"),
+        }),
+    }
+}
+
+/// Run the check `check`, noting in `spans` when, for how long and whether it failed.
+fn timed<T>(
+    spans: &mut Vec<Span>,
+    check: &str,
+    run: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let start = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let started = std::time::Instant::now();
+    let result = run();
+    spans.push(Span {
+        check: check.to_string(),
+        start,
+        duration: started.elapsed(),
+        error: result.as_ref().err().map(|err| format!("{err:#}")),
+    });
+    result
+}
+
+/// Send the checks' timings to the journal when running under systemd.
+fn log_spans(spans: &[Span]) {
+    if journal_connected() {
+        for span in spans {
+            // a timing that doesn't make it isn't worth an error of its own
+            let _ = journal::send(
+                std::path::Path::new(journal::SOCKET),
+                &journal::entry(&journal::span_fields(span)),
+            );
+        }
+    }
+}
+
+/// Send the checks' timings to the OpenTelemetry collector if there is one, from a thread of its
+/// own that's done within `otlp::TIMEOUT`. It being down is reported, but doesn't stop anything.
+fn export_spans(spans: &[Span]) -> Option<std::thread::JoinHandle<()>> {
+    let endpoint = OTLP_ENDPOINT?;
+    if spans.is_empty() {
+        return None;
+    }
+    let host = std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+    let body = otlp::body(spans, host.trim(), &otlp::Ids::random(spans.len()));
+    Some(std::thread::spawn(move || {
+        if let Err(err) = otlp::export(endpoint, &body, otlp::TIMEOUT) {
+            log_error(&format!("{err:#}"));
+        }
+    }))
+}
+
+/// Whether stderr goes to the journal, i.e. this is a systemd service.
+fn journal_connected() -> bool {
+    journal::connected(
+        std::env::var("JOURNAL_STREAM").ok().as_deref(),
+        std::path::Path::new("/proc/self/fd/2"),
+    )
+}
+
+/// Report something that went wrong without stopping the bar, as an error entry in the journal
+/// when running under systemd, otherwise on stderr.
+fn log_error(message: &str) {
+    if journal_connected() {
+        let entry = journal::entry(&[
+            ("MESSAGE", message.to_string()),
+            ("PRIORITY", "3".to_string()),
+            (
+                "SYSLOG_IDENTIFIER",
+                "i3status-nix-update-widget".to_string(),
+            ),
+        ]);
+        if journal::send(std::path::Path::new(journal::SOCKET), &entry).is_ok() {
+            return;
+        }
     }
+    eprintln!("nix-update: {message}");
+}
 
-    let code = BarCommand {
+fn found(findings: &mut Vec<Finding>, label: &str, items: Vec<String>) {
+    if !items.is_empty() {
+        findings.push(Finding {
+            label: label.to_string(),
+            items,
+        });
+    }
+}
+
+/// Run the hooks for whatever changed since they last ran. A broken hook is reported, but doesn't
+/// stop the bar from showing.
+fn fire_hooks(report: &Report, code: &BarCommand) {
+    let hooks = [ON_STATE_CHANGE, ON_REBOOT_NEEDED, ON_UPDATE_DETECTED];
+    if hooks.iter().all(Option::is_none) || stateless() {
+        return;
+    }
+    let Some(state_dir) = user_state_dir() else {
+        return;
+    };
+
+    let mut seen = hooks::Seen {
+        state: code.state,
+        reboot_needed: report
+            .findings
+            .iter()
+            .any(|finding| finding.label.starts_with("Reboot")),
+        modified_date: MODIFIED_DATE,
+        state_fired_at: None,
+        reboot_fired_at: None,
+    };
+    let previous = state_dir.read::<hooks::Seen>();
+    let due = hooks::due(
+        previous.as_ref(),
+        &mut seen,
+        SystemClock.now().timestamp(),
+        &RENOTIFY,
+    );
+    if previous.as_ref() == Some(&seen) {
+        return;
+    }
+    if let Err(err) = state_dir.write(&seen) {
+        log_error(&format!("{err:#}"));
+        return;
+    }
+
+    let status = serde_json::json!({
+        "state": code.state,
+        "text": code.text,
+        "findings": report.findings,
+    })
+    .to_string();
+    for hook in due {
+        let command = match hook {
+            hooks::Hook::StateChange => ON_STATE_CHANGE,
+            hooks::Hook::RebootNeeded => ON_REBOOT_NEEDED,
+            hooks::Hook::UpdateDetected => ON_UPDATE_DETECTED,
+        };
+        if let Some(command) = command {
+            if let Err(err) = hooks::run(command, &status) {
+                log_error(&format!("{err:#}"));
+            }
+        }
+    }
+}
+
+/// The state the block takes and which check it's down to. Anything a check found is a warning,
+/// unless the item rules say otherwise.
+fn overall(report: &Report) -> (&str, State) {
+    let states = report
+        .states
+        .iter()
+        .map(|(cause, state)| (cause.as_str(), *state))
+        .chain(
+            report
+                .findings
+                .iter()
+                .map(|finding| (finding.label.as_str(), rules::state(finding, ITEM_RULES))),
+        );
+    worst(states).unwrap_or(("age", State::Good))
+}
+
+fn bar_command(report: &Report) -> BarCommand {
+    let mut text = match report.action {
+        Some(action) => format!("{}: {}", action.advice(), report.text),
+        None => report.text.clone(),
+    };
+
+    for finding in &report.findings {
+        text.push(' ');
+        match COLLAPSE_MODULES {
+            Some(limit) => text
+                .push_str(&modules::collapse(finding, PAIRED_MODULES, limit).render(INLINE_LIMIT)),
+            None => text.push_str(&finding.render(INLINE_LIMIT)),
+        }
+    }
+
+    let state = overall(report).1;
+    // an empty block is how bars are told to hide it (hide_when_empty in i3status-rust)
+    if state < MIN_SEVERITY {
+        text.clear();
+    }
+
+    BarCommand {
         icon: STATUS_ICON.to_string(),
-        state: status,
-        text: format!("Age: {}", duration_days),
+        state,
+        text: truncate(&text, MAX_WIDTH),
+    }
+}
+
+/// Whether this build shows nothing but the age as an i3status-rust block, so none of the
+/// general machinery is needed.
+fn only_age() -> bool {
+    OUTPUT_FORMAT == "i3status-rs"
+        && EXTRA_FIELDS == "{}"
+        && FIELD_NAMES.is_empty()
+        && MIN_SEVERITY == State::Good
+        && matches!(STATE_NAMES, StateNames::Variant)
+        && MAX_WIDTH == 0
+        && AGE_DISPLAY == ["age"]
+        && AGE_LANGUAGE.is_none()
+        && !CALENDAR_DAYS
+        && !IDLE_AWARE
+        && MAINTENANCE_CALENDAR.is_none()
+        && INSTALL_GRACE_DAYS.is_none()
+        && DEPLOYED_BY.is_none()
+        && DEPLOY_MARKER.is_none()
+        && THRESHOLD_SCHEDULE.is_empty()
+        && TRACKED_FLAKES.is_empty()
+        && !SHOW_UPTIME
+        && !SHOW_KERNEL_AGE
+        && PRIVILEGED_CHECKS == "local"
+        && CHECKS.iter().all(|(_, built)| !built)
+        && REBOOT_THRESHOLDS.is_none()
+        && OTLP_ENDPOINT.is_none()
+        && !SHOW_ACTION
+        && !RECOMMENDATION_TEXT
+        && SHOW_UPDATE_DAYS.is_none()
+        && [ON_STATE_CHANGE, ON_REBOOT_NEEDED, ON_UPDATE_DETECTED]
+            .iter()
+            .all(Option::is_none)
+        // the icon goes into the JSON as is, so anything JSON escapes takes the full path
+        && !STATUS_ICON.contains(|c: char| c == '"' || c == '\\' || c.is_control())
+        // so a theme that doesn't exist is the same error either way
+        && theme::select(THEME, false).is_ok()
+}
+
+/// The same block the full path prints for an age-only build, without reading any files or
+/// allocating, for bars that run the widget every second.
+fn print_age_only() -> anyhow::Result<()> {
+    let age = lock_age(&SystemClock, MODIFIED_DATE)?;
+    write_age_only(
+        &mut std::io::stdout().lock(),
+        STATUS_ICON,
+        &age,
+        &THRESHOLDS,
+        SHOW_CLOCK_SKEW_HINT,
+    )?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    if std::env::args_os().len() == 1 && only_age() {
+        return print_age_only();
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let no_exec = NO_EXEC || args.iter().any(|arg| arg == "--no-exec");
+    let simulation = match args.iter().position(|arg| arg == "--simulate") {
+        Some(at) => Some(Simulation::parse(
+            args.get(at + 1)
+                .context("--simulate needs results, e.g. age=20,reboot=kernel")?,
+        )?),
+        None => None,
     };
 
+    // run as root by a service, so the bar itself doesn't need to be
+    if args.first().map(String::as_str) == Some("publish") {
+        let mut spans = Vec::new();
+        let findings = privileged_findings(no_exec, &disabled_checks(), &mut spans);
+        log_spans(&spans);
+        // a service with nothing waiting on it, so the export can be seen through
+        if let Some(export) = export_spans(&spans) {
+            let _ = export.join();
+        }
+        return Ok(system_state_dir().write(&Published {
+            timestamp: SystemClock.now().timestamp(),
+            findings: findings?,
+        })?);
+    }
+
+    if let Some(toggle @ ("disable" | "enable")) = args.first().map(String::as_str) {
+        return set_check(toggle == "disable", &args[1..]);
+    }
+
+    // run from the activation script, as root
+    if args.first().map(String::as_str) == Some("write-marker") {
+        let current = std::path::Path::new(reboot::CURRENT_SYSTEM);
+        let marker = marker::Marker {
+            generation: marker::generation(std::path::Path::new(boot::SYSTEM_PROFILE)),
+            system: std::fs::canonicalize(current).context("Could not resolve current system")?,
+            timestamp: SystemClock.now().timestamp(),
+            changed: changed_since_boot(no_exec)?,
+        };
+        return Ok(system_state_dir().write(&marker)?);
+    }
+
+    let theme = theme::select(THEME, std::env::var_os("NO_COLOR").is_some())?;
+
+    if args.first().map(String::as_str) == Some("report") {
+        return bug_report(no_exec);
+    }
+
+    // run on whichever machine collected the other hosts' --details --json
+    if args.first().map(String::as_str) == Some("aggregate") {
+        let json = args.iter().any(|arg| arg == "--json");
+        let hosts = aggregate::rank(
+            args[1..]
+                .iter()
+                .filter(|arg| *arg != "--json")
+                .map(|path| aggregate::load(std::path::Path::new(path)))
+                .collect(),
+        );
+        if json {
+            println!("{}", serde_json::to_string(&hosts)?);
+        } else {
+            print!("{}", aggregate::table(&hosts));
+        }
+        return Ok(());
+    }
+
+    // the same ranked table, but asking the hosts over ssh
+    if args.first().map(String::as_str) == Some("remote") {
+        if no_exec {
+            anyhow::bail!("remote checks run ssh, which no exec rules out");
+        }
+        let json = args.iter().any(|arg| arg == "--json");
+        let specs = args[1..]
+            .iter()
+            .filter(|arg| *arg != "--json")
+            .cloned()
+            .collect::<Vec<_>>();
+        let ssh_config = std::env::var_os("HOME")
+            .map(|home| std::path::PathBuf::from(home).join(".ssh/config"))
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let hosts = remote::expand(&specs, &remote::ssh_config_hosts(&ssh_config), |path| {
+            std::fs::read_to_string(path)
+        })
+        .context("Could not read the hosts file")?;
+        let timeout = format!("ConnectTimeout={REMOTE_TIMEOUT}");
+        let hosts = aggregate::rank(remote::check_all(
+            &hosts,
+            &[
+                "ssh",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                &timeout,
+                "--",
+                "{host}",
+                "i3status-nix-update-widget",
+                "agent",
+            ],
+            REMOTE_PARALLEL,
+            std::time::Duration::from_secs(REMOTE_TIMEOUT),
+        ));
+        if json {
+            println!("{}", serde_json::to_string(&hosts)?);
+        } else {
+            print!("{}", aggregate::table(&hosts));
+        }
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some("doctor") {
+        doctor(no_exec);
+        return Ok(());
+    }
+
+    if args.first().map(String::as_str) == Some("summary") {
+        return summary(no_exec);
+    }
+
+    if args.first().map(String::as_str) == Some("preview") {
+        return preview(theme);
+    }
+
+    if let Some(wrap_at) = args.iter().position(|arg| arg == "--wrap") {
+        return Ok(wrap::run(
+            &args[wrap_at + 1..],
+            no_exec,
+            WRAP_POSITION,
+            std::time::Duration::from_secs(WRAP_INTERVAL),
+            EXTRA_FIELDS,
+            || {
+                // a broken check shouldn't take the rest of the bar down with it
+                report(no_exec, simulation.as_ref(), true)
+                    .map(|report| {
+                        let code = bar_command(&report);
+                        if !no_exec {
+                            fire_hooks(&report, &code);
+                        }
+                        style(theme)
+                            .i3bar_block(&code)
+                            .blinking(report.overdue_days, BLINK_AFTER)
+                    })
+                    .unwrap_or_else(|err| {
+                        let code = BarCommand {
+                            icon: STATUS_ICON.to_string(),
+                            state: State::Critical,
+                            text: truncate(&format!("nix-update: {err:#}"), MAX_WIDTH),
+                        };
+                        style(theme).i3bar_block(&code)
+                    })
+            },
+            || IDLE_AWARE && !no_exec && session::idle().unwrap_or(false),
+        )?);
+    }
+
+    if args.first().map(String::as_str) == Some("agent") {
+        // checked afresh for every request, the agent may be left running
+        agent::serve(std::io::stdin().lock(), std::io::stdout().lock(), || {
+            details_json(report(no_exec, simulation.as_ref(), false))
+        })?;
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--details") && args.iter().any(|arg| arg == "--json") {
+        println!(
+            "{}",
+            details_json(report(no_exec, simulation.as_ref(), false))
+        );
+        return Ok(());
+    }
+
+    let report = report(no_exec, simulation.as_ref(), false)?;
+
+    if args.iter().any(|arg| arg == "--details") {
+        println!("{}", report.text);
+        let (cause, state) = overall(&report);
+        println!("State: {state:?} from {cause}");
+        if let Some(updated) = &report.updated {
+            println!("{updated}");
+        }
+        for finding in &report.findings {
+            print!("{}", finding.render_details());
+        }
+        for note in &report.notes {
+            println!("Note: {note}");
+        }
+        return Ok(());
+    }
+
+    let code = bar_command(&report);
+    if !no_exec {
+        fire_hooks(&report, &code);
+    }
+
+    if let Some(textfile) = args.iter().position(|arg| arg == "--textfile") {
+        let path = args.get(textfile + 1).context("--textfile needs a path")?;
+        if stateless() {
+            anyhow::bail!("--textfile writes a file, which --stateless rules out");
+        }
+        let input_ages = INPUT_DATES
+            .iter()
+            .map(|(input, date)| Ok((*input, lock_age(&SystemClock, *date)?.days)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        return Ok(state_dir::write_atomically(
+            std::path::Path::new(path),
+            &metrics::render(report.age_days, &input_ages, code.state, &report.findings),
+        )?);
+    }
+
     println!(
         "{}",
-        serde_json::to_string(&code).context("Could not serialize status")?
+        style(theme).render(OUTPUT_FORMAT, &code, &tooltip(&report))?
     );
 
     Ok(())
 }
+
+/// How this build writes its block, in `theme`.
+fn style(theme: &theme::Theme) -> Style<'_> {
+    Style {
+        state_names: &STATE_NAMES,
+        i3bar_layout: &I3BAR_LAYOUT,
+        theme,
+        i3bar_colors: I3BAR_COLORS,
+        field_names: FIELD_NAMES,
+        extra_fields: EXTRA_FIELDS,
+    }
+}
+
+fn tooltip(report: &Report) -> Tooltip<'_> {
+    Tooltip {
+        findings: &report.findings,
+        updated: report.updated.as_deref(),
+        percentage: report.percentage,
+    }
+}
+
+/// Print what every state looks like in every format with this build's settings, using made up
+/// ages and findings.
+fn preview(theme: &theme::Theme) -> anyhow::Result<()> {
+    let reboot = [Finding {
+        label: "Reboot".to_string(),
+        items: vec!["kernel".to_string()],
+    }];
+    let updated = "updated today: nixpkgs +9d";
+    let lines = style(theme).preview(|state| {
+        let (age_days, findings): (_, &[Finding]) = match state {
+            State::Good => (0, &[]),
+            State::Info => (THRESHOLDS.good + 1, &[]),
+            State::Warning => (THRESHOLDS.update, &reboot),
+            State::Critical => (THRESHOLDS.out_of_date, &reboot),
+        };
+        let percentage = waybar::percentage(age_days, THRESHOLDS.out_of_date);
+        let report = Report {
+            age_days,
+            percentage,
+            overdue_days: age_days - THRESHOLDS.out_of_date,
+            updated: Some(updated.to_string()),
+            action: (!findings.is_empty() && SHOW_ACTION).then_some(Action::Reboot),
+            recommendation: if findings.is_empty() {
+                "all good"
+            } else {
+                "reboot"
+            },
+            states: vec![("age".to_string(), state)],
+            text: format!("Age: {age_days}"),
+            findings: findings.to_vec(),
+            notes: Vec::new(),
+        };
+        let tooltip = Tooltip {
+            findings,
+            updated: Some(updated),
+            percentage,
+        };
+        (bar_command(&report), tooltip)
+    })?;
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// A digest of where things stand and what changed in the last week, for a weekly timer to hand to
+/// notify-send or mail, for people who don't look at their bar much.
+fn summary(no_exec: bool) -> anyhow::Result<()> {
+    let report = report(no_exec, None, false)?;
+    let (cause, state) = overall(&report);
+    println!("{state:?} ({cause}): {}", report.text);
+    for finding in &report.findings {
+        println!("{}: {}", finding.label, finding.items.join(", "));
+    }
+
+    let history = user_state_dir().and_then(|dir| dir.read::<lock_diff::LockHistory>());
+    println!(
+        "{}",
+        lock_diff::this_week(history.as_ref(), SystemClock.now().timestamp())
+    );
+    Ok(())
+}
+
+/// Everything useful in an issue about a wrong result: versions, the build's settings, the systems
+/// involved, what each part of the checks found and how long it took, what's in the state
+/// directories and the last lines logged to the journal.
+fn bug_report(no_exec: bool) -> anyhow::Result<()> {
+    println!("i3status-nix-update-widget {}", env!("CARGO_PKG_VERSION"));
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+    println!("kernel: {}", kernel.trim());
+    for system in [
+        reboot::BOOTED_SYSTEM,
+        reboot::CURRENT_SYSTEM,
+        boot::SYSTEM_PROFILE,
+    ] {
+        match std::fs::canonicalize(system) {
+            Ok(target) => println!("{system}: {}", target.display()),
+            Err(err) => println!("{system}: {err}"),
+        }
+    }
+
+    println!("\nsettings:");
+    // extra fields are free-form and could hold anything, so only their names are shown
+    let extra_fields =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(EXTRA_FIELDS)
+            .map(|fields| fields.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+    let settings: [(&str, String); 20] = [
+        ("lock date", MODIFIED_DATE.to_string()),
+        (
+            "thresholds",
+            format!("{GOOD_THRESHOLD}/{UPDATE_THRESHOLD}/{OUT_OF_DATE_THRESHOLD}"),
+        ),
+        ("schedule windows", THRESHOLD_SCHEDULE.len().to_string()),
+        ("format", OUTPUT_FORMAT.to_string()),
+        ("theme", format!("{THEME}, i3bar colors {I3BAR_COLORS}")),
+        (
+            "privileged checks",
+            format!("{PRIVILEGED_CHECKS}, stale after {PUBLISHED_MAX_HOURS}h"),
+        ),
+        ("no exec", no_exec.to_string()),
+        (
+            "state dirs",
+            format!(
+                "{}, {:?}",
+                system_state_dir().path.display(),
+                user_state_dir().map(|dir| dir.path)
+            ),
+        ),
+        ("boot check", CHECK_BOOT_FILES.to_string()),
+        (
+            "reboot check",
+            format!(
+                "{CHECK_REBOOT} ({REBOOT_BACKEND}, {} probes)",
+                REBOOT_PROBES.len()
+            ),
+        ),
+        (
+            "reboot-required",
+            format!("write {WRITE_REBOOT_REQUIRED}, read {READ_REBOOT_REQUIRED}"),
+        ),
+        ("unit check", CHECK_UNITS.to_string()),
+        ("etc paths", format!("{ETC_PATHS:?}")),
+        ("failed unit check", CHECK_FAILED_UNITS.to_string()),
+        ("secure boot check", CHECK_SECURE_BOOT.to_string()),
+        ("paired modules", format!("{PAIRED_MODULES:?}")),
+        (
+            "deleted store path check",
+            CHECK_DELETED_STORE_PATHS.to_string(),
+        ),
+        (
+            "nix checks",
+            format!("{NIX_VERSION_FLOOR:?}, daemon {CHECK_NIX_DAEMON}"),
+        ),
+        ("tracked flakes", TRACKED_FLAKES.len().to_string()),
+        ("extra fields", format!("{extra_fields:?}")),
+    ];
+    for (name, value) in settings {
+        println!("  {name}: {value}");
+    }
+
+    println!();
+    let started = std::time::Instant::now();
+    let report = report(no_exec, None, false);
+    println!("report: {:?}", started.elapsed());
+    match report {
+        Ok(report) => {
+            println!("  {}", report.text);
+            for finding in &report.findings {
+                print!("  {}", finding.render_details());
+            }
+        }
+        Err(err) => println!("  error: {err:#}"),
+    }
+
+    if PRIVILEGED_CHECKS == "published" {
+        match system_state_dir().read::<Published>() {
+            Some(published) => println!("published at {}", published.timestamp),
+            None => println!("nothing published"),
+        }
+    }
+
+    println!("\nstate:");
+    for dir in std::iter::once(system_state_dir()).chain(user_state_dir()) {
+        print!("{}", bug_report::state_snapshot(&dir.path));
+    }
+
+    println!("\nlog:");
+    if no_exec {
+        println!("  not read, journalctl can't be run with no exec");
+    } else {
+        match bug_report::journal_lines("journalctl", 50) {
+            Ok(lines) => print!("{lines}"),
+            Err(err) => println!("  {err:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Where root keeps what it publishes and the marker, unless the build says otherwise (e.g. under
+/// /persist on an impermanent root).
+fn system_state_dir() -> StateDir {
+    let dir = StateDir::system_or(SYSTEM_STATE_DIR);
+    if stateless() {
+        dir.read_only()
+    } else {
+        dir
+    }
+}
+
+/// Where the user's hook and lock history state goes, unless the build says otherwise.
+fn user_state_dir() -> Option<StateDir> {
+    let dir = StateDir::user_or(USER_STATE_DIR)?;
+    Some(if stateless() { dir.read_only() } else { dir })
+}
+
+/// The checks switched off with `disable`, none if there's no state directory to keep them in.
+fn disabled_checks() -> toggles::Disabled {
+    toggles::Disabled::load(user_state_dir())
+}
+
+/// `disable CHECK [--hours N]` or `enable CHECK`, kept in the user state directory (root's for the
+/// published checks).
+fn set_check(disable: bool, args: &[String]) -> anyhow::Result<()> {
+    let check = args.first().context("Which check, e.g. failed?")?;
+    if !CHECKS.iter().any(|(name, _)| name == check) {
+        let names = CHECKS.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        anyhow::bail!("Unknown check {check}, one of {}", names.join(", "));
+    }
+    let until = match args.iter().position(|arg| arg == "--hours") {
+        Some(at) => {
+            let hours = args
+                .get(at + 1)
+                .and_then(|hours| hours.parse::<i64>().ok())
+                .context("--hours needs a number")?;
+            Some(SystemClock.now().timestamp() + hours * 60 * 60)
+        }
+        None => None,
+    };
+
+    let state_dir = user_state_dir().context("No state directory to keep disabled checks in")?;
+    let mut disabled = state_dir.read::<toggles::Disabled>().unwrap_or_default();
+    if disable {
+        disabled.disable(check, until);
+    } else {
+        disabled.enable(check);
+    }
+    Ok(state_dir.write(&disabled)?)
+}
+
+/// Whether nothing may be written at all, for kiosks and live ISOs. Hooks then never fire, as
+/// there's nowhere to remember they already did.
+fn stateless() -> bool {
+    STATELESS || std::env::args().any(|arg| arg == "--stateless")
+}
+
+/// What this environment lets the checks do, for finding out what a hardened service or a mandatory
+/// access control profile denies.
+fn doctor(no_exec: bool) {
+    let denied = |why: Option<String>| match why {
+        Some(why) => format!("denied ({why})"),
+        None => "ok".to_string(),
+    };
+
+    let exec = if no_exec {
+        "not tried, no exec".to_string()
+    } else {
+        // itself as an agent with nothing to answer, which exits straight away
+        let program = std::env::current_exe().unwrap_or_else(|_| "/proc/self/exe".into());
+        match doctor::try_exec(&program, &["agent"]) {
+            doctor::Exec::Allowed => "ok".to_string(),
+            doctor::Exec::Missing => format!("not tried, {} is missing", program.display()),
+            doctor::Exec::Denied(why) => format!("denied ({why})"),
+        }
+    };
+    println!("run programs: {exec}");
+
+    for path in [
+        reboot::BOOTED_SYSTEM,
+        reboot::CURRENT_SYSTEM,
+        boot::SYSTEM_PROFILE,
+        "/proc/self/maps",
+        // other users' processes, for the deleted store path check
+        "/proc/1/maps",
+        secureboot::ESP_EFI_DIR,
+    ] {
+        let path = std::path::Path::new(path);
+        let read = match path.try_exists() {
+            // not a denial, just not NixOS or no ESP there
+            Ok(false) => "missing".to_string(),
+            _ => denied(doctor::read_denied(path)),
+        };
+        println!("read {}: {read}", path.display());
+    }
+
+    for dir in std::iter::once(system_state_dir()).chain(user_state_dir()) {
+        let write = if dir.read_only {
+            "not tried, stateless".to_string()
+        } else {
+            denied(doctor::write_denied(&dir.path))
+        };
+        println!("write {}: {write}", dir.path.display());
+    }
+}
+
+/// `time` in the configured timezone.
+fn in_timezone(
+    time: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<chrono::DateTime<chrono::FixedOffset>> {
+    Ok(match TIMEZONE {
+        "local" => time.with_timezone(&chrono::Local).fixed_offset(),
+        timezone => time.with_timezone(&parse_timezone(timezone)?),
+    })
+}
+
+/// Unix time the running kernel was built at, or failing that (as with any reproducible build) when
+/// it arrived in the store. `None` if neither is known.
+fn kernel_built(no_exec: bool) -> anyhow::Result<Option<i64>> {
+    let proc_version =
+        std::fs::read_to_string("/proc/version").context("Could not read /proc/version")?;
+    if let Some(built) = kernel_age::build_date(&proc_version) {
+        return Ok(Some(built.timestamp()));
+    }
+    if no_exec {
+        return Ok(None);
+    }
+
+    let kernel = std::path::Path::new(reboot::BOOTED_SYSTEM).join("kernel");
+    let kernel = std::fs::canonicalize(&kernel)
+        .with_context(|| format!("Could not resolve {}", kernel.display()))?;
+    let Some(store_path) = kernel
+        .ancestors()
+        .find(|path| path.parent() == Some(std::path::Path::new("/nix/store")))
+    else {
+        return Ok(None);
+    };
+    Ok(kernel_age::registered(&store_path.to_string_lossy())?)
+}
+
+/// Unix time the system booted at.
+fn booted_at() -> anyhow::Result<i64> {
+    let proc_uptime =
+        std::fs::read_to_string("/proc/uptime").context("Could not read /proc/uptime")?;
+    Ok(SystemClock.now().timestamp() - parse_uptime(&proc_uptime)?.num_seconds())
+}
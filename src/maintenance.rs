@@ -0,0 +1,96 @@
+// an update planned for saturday shouldn't nag all week, but if saturday came and went without one
+// it should stop being patient. maintenance windows come from an ICS calendar file.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// One planned maintenance window.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Maintenance {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// The windows in the VEVENTs of `ics`. Times without a `Z` (floating, or with a TZID we don't
+/// have a database for) are taken to be at `offset`. Events we can't make sense of are skipped.
+pub fn parse_ics(ics: &str, offset: &FixedOffset) -> Vec<Maintenance> {
+    // long lines are folded by breaking them and starting the continuation with a space or tab
+    let unfolded = ics
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut windows = Vec::new();
+    let (mut start, mut end) = (None, None);
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (property, params) = name.split_once(';').unwrap_or((name, ""));
+        match property {
+            "BEGIN" if value == "VEVENT" => (start, end) = (None, None),
+            "DTSTART" => start = parse_time(value, params, offset),
+            "DTEND" => end = parse_time(value, params, offset),
+            "END" if value == "VEVENT" => {
+                if let (Some(start), Some(end)) = (start, end) {
+                    windows.push(Maintenance { start, end });
+                }
+            }
+            _ => {}
+        }
+    }
+    windows
+}
+
+fn parse_time(value: &str, params: &str, offset: &FixedOffset) -> Option<DateTime<Utc>> {
+    if params.contains("VALUE=DATE") {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return local(date.and_hms_opt(0, 0, 0)?, offset);
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => Some(
+            NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                .ok()?
+                .and_utc(),
+        ),
+        None => local(
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            offset,
+        ),
+    }
+}
+
+fn local(time: NaiveDateTime, offset: &FixedOffset) -> Option<DateTime<Utc>> {
+    Some(
+        offset
+            .from_local_datetime(&time)
+            .single()?
+            .with_timezone(&Utc),
+    )
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Planned {
+    /// A window is on now, so there's no point nagging.
+    During,
+    /// The last window is over and the lock wasn't updated since it started.
+    Missed,
+    Nothing,
+}
+
+/// Where `now` stands with the `windows`, for a lock last modified at `locked`.
+pub fn planned(windows: &[Maintenance], now: DateTime<Utc>, locked: DateTime<Utc>) -> Planned {
+    if windows
+        .iter()
+        .any(|window| window.start <= now && now < window.end)
+    {
+        return Planned::During;
+    }
+    let last = windows
+        .iter()
+        .filter(|window| window.end <= now)
+        .max_by_key(|window| window.end);
+    match last {
+        Some(window) if locked < window.start => Planned::Missed,
+        _ => Planned::Nothing,
+    }
+}
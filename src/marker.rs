@@ -0,0 +1,44 @@
+// the activation script already knows what changed when it runs, so it can leave the answer
+// where the bar can pick it up with a single read instead of comparing systems on every refresh.
+
+use crate::state_dir::Stored;
+use std::path::{Path, PathBuf};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// Number of the system profile generation, if it could be worked out.
+    pub generation: Option<u64>,
+    /// Store path the current system resolved to when the marker was written.
+    pub system: PathBuf,
+    /// Unix timestamp of when the marker was written.
+    pub timestamp: i64,
+    /// Components that need a reboot to take effect.
+    pub changed: Vec<String>,
+}
+
+impl Stored for Marker {
+    const NAME: &'static str = "marker.json";
+    const VERSION: u32 = 1;
+}
+
+impl Marker {
+    /// Whether the marker still describes this boot of `current_system`: written after booting
+    /// (at unix time `booted_at`) and for the system that's still current.
+    pub fn is_current(&self, current_system: &Path, booted_at: i64) -> bool {
+        self.timestamp >= booted_at
+            && std::fs::canonicalize(current_system).is_ok_and(|system| system == self.system)
+    }
+}
+
+/// The generation number a system profile link like `system` -> `system-42-link` points at.
+pub fn generation(system_profile: &Path) -> Option<u64> {
+    std::fs::read_link(system_profile)
+        .ok()?
+        .file_name()?
+        .to_str()?
+        .strip_suffix("-link")?
+        .rsplit('-')
+        .next()?
+        .parse()
+        .ok()
+}
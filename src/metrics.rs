@@ -0,0 +1,97 @@
+// prometheus textfile output, for node_exporter's textfile collector. a systemd timer runs the
+// widget with --textfile and node_exporter picks the result up on its next scrape.
+
+use crate::{Finding, State};
+use std::fmt::Write;
+
+/// Render the metrics in the prometheus text exposition format.
+/// `input_ages` are the ages in days of the individual flake inputs.
+pub fn render(
+    age_days: i64,
+    input_ages: &[(&str, i64)],
+    state: State,
+    findings: &[Finding],
+) -> String {
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "nix_update_flake_age_days",
+        "Days since the most recently updated flake input was last modified.",
+    );
+    writeln!(out, "nix_update_flake_age_days {age_days}").unwrap();
+
+    metric(
+        &mut out,
+        "nix_update_flake_input_age_days",
+        "Days since each flake input was last modified.",
+    );
+    for (input, age) in input_ages {
+        writeln!(
+            out,
+            "nix_update_flake_input_age_days{{input=\"{}\"}} {age}",
+            escape_label(input)
+        )
+        .unwrap();
+    }
+
+    metric(
+        &mut out,
+        "nix_update_state",
+        "1 for the state the widget is currently in, 0 for the others.",
+    );
+    for candidate in [State::Info, State::Good, State::Warning, State::Critical] {
+        writeln!(
+            out,
+            "nix_update_state{{state=\"{candidate:?}\"}} {}",
+            u8::from(candidate == state)
+        )
+        .unwrap();
+    }
+
+    metric(
+        &mut out,
+        "nix_update_findings",
+        "Number of items each check found needing attention.",
+    );
+    for finding in findings {
+        writeln!(
+            out,
+            "nix_update_findings{{check=\"{}\"}} {}",
+            escape_label(&finding.label),
+            finding.items.len()
+        )
+        .unwrap();
+    }
+
+    metric(
+        &mut out,
+        "nix_update_pending_component",
+        "1 for each item a check found needing attention.",
+    );
+    for finding in findings {
+        for item in &finding.items {
+            writeln!(
+                out,
+                "nix_update_pending_component{{check=\"{}\",name=\"{}\"}} 1",
+                escape_label(&finding.label),
+                escape_label(item)
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+fn metric(out: &mut String, name: &str, help: &str) {
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} gauge").unwrap();
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
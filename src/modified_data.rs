@@ -1,5 +1,79 @@
 const MODIFIED_DATE: i64 = 1697089731;
+const INPUT_DATES: &[(&str, i64)] = &[];
+const TRACKED_FLAKES: &[Tracked] = &[];
 const GOOD_THRESHOLD: i64 = 3;
 const UPDATE_THRESHOLD: i64 = 4;
 const OUT_OF_DATE_THRESHOLD: i64 = 14;
 const STATUS_ICON: &str = "cogs";
+const TIMEZONE: &str = "local";
+const CALENDAR_DAYS: bool = false;
+const MAINTENANCE_CALENDAR: Option<&str> = None;
+const INSTALL_GRACE_DAYS: Option<i64> = None;
+const DEPLOYED_BY: Option<&str> = None;
+const DEPLOY_MARKER: Option<&str> = None;
+const SHOW_UPDATE_DAYS: Option<i64> = None;
+const AGE_DISPLAY: &[&str] = &["age"];
+const AGE_LANGUAGE: Option<&str> = None;
+const SHOW_CLOCK_SKEW_HINT: bool = true;
+const SHOW_UPTIME: bool = false;
+const SHOW_KERNEL_AGE: bool = false;
+const CHECK_BOOT_FILES: bool = false;
+const CHECK_REBOOT: bool = false;
+const REBOOT_BACKEND: &str = "builtin";
+const REBOOT_PROBES: &[Probe] = &[];
+const WRITE_REBOOT_REQUIRED: bool = false;
+const READ_REBOOT_REQUIRED: bool = false;
+const CHECK_UNITS: bool = false;
+const CHECK_GC_ROOTS: bool = false;
+const REBOOT_THRESHOLDS: Option<Thresholds> = None;
+const ETC_PATHS: &[&str] = &[];
+const CHECK_FAILED_UNITS: bool = false;
+const OTLP_ENDPOINT: Option<&str> = None;
+const SHOW_ACTION: bool = false;
+const RECOMMENDATION_TEXT: bool = false;
+const CHECK_SECURE_BOOT: bool = false;
+const SBCTL: &str = "sbctl";
+const MODINFO: &str = "modinfo";
+const PAIRED_MODULES: &[&str] = &[];
+const ITEM_RULES: &[Rule] = &[];
+const GENERIC_MODULE_CHANGES: bool = false;
+const COLLAPSE_MODULES: Option<usize> = None;
+const CHECK_DELETED_STORE_PATHS: bool = false;
+const PROJECT_THRESHOLD: Option<i64> = None;
+const REGISTRY_THRESHOLD: Option<i64> = None;
+const NIX_VERSION_FLOOR: Option<&str> = None;
+const CHECK_NIX_DAEMON: bool = false;
+const MIN_SEVERITY: State = State::Good;
+const INLINE_LIMIT: usize = 3;
+const MAX_WIDTH: usize = 0;
+const OUTPUT_FORMAT: &str = "i3status-rs";
+const I3BAR_NAME: Option<&str> = Some("nix-update");
+const I3BAR_INSTANCE: Option<&str> = None;
+const I3BAR_MIN_WIDTH: Option<&str> = None;
+const I3BAR_ALIGN: Option<&str> = None;
+const I3BAR_SEPARATOR_BLOCK_WIDTH: Option<u32> = None;
+const STATE_NAMES: StateNames = StateNames::Variant;
+const FIELD_NAMES: &[(&str, &str)] = &[];
+const EXTRA_FIELDS: &str = r#"{}"#;
+const WRAP_POSITION: usize = 0;
+const WRAP_INTERVAL: u64 = 300;
+const REMOTE_PARALLEL: usize = 8;
+const REMOTE_TIMEOUT: u64 = 20;
+const BLINK_AFTER: Option<i64> = None;
+const IDLE_AWARE: bool = false;
+const THRESHOLD_SCHEDULE: &[Window] = &[];
+const THEME: &str = "default";
+const I3BAR_COLORS: bool = false;
+const PRIVILEGED_CHECKS: &str = "local";
+const PUBLISHED_MAX_HOURS: i64 = 6;
+const NO_EXEC: bool = false;
+const STATELESS: bool = false;
+const SYSTEM_STATE_DIR: Option<&str> = None;
+const USER_STATE_DIR: Option<&str> = None;
+const ON_STATE_CHANGE: Option<&str> = None;
+const ON_REBOOT_NEEDED: Option<&str> = None;
+const ON_UPDATE_DETECTED: Option<&str> = None;
+const RENOTIFY: hooks::Renotify = hooks::Renotify {
+    critical: None,
+    reboot: None,
+};
@@ -0,0 +1,282 @@
+// out-of-tree modules like zfs are built against one exact kernel. if the latest generation pairs a
+// kernel with a module built for a different one (or none at all), rebooting into it won't be able
+// to load the module, which for zfs means no root pool.
+
+use crate::error::{Error, IoContext, Result};
+use crate::Finding;
+use std::path::{Path, PathBuf};
+
+/// Modules out of `names` that `system` doesn't ship built for its own kernel. A module built for
+/// another kernel still ends up in the module tree, but under that kernel's version, so only the
+/// running kernel's own directory is looked in.
+pub fn mismatched_modules(system: &Path, names: &[&str]) -> Result<Vec<String>> {
+    let version = kernel_version(system).ok_or_else(|| Error::SystemProfile {
+        context: format!("Could not tell which kernel {} boots", system.display()),
+        source: None,
+    })?;
+    let dir = system.join("kernel-modules/lib/modules").join(&version);
+
+    Ok(names
+        .iter()
+        .filter(|name| {
+            !find_module(&dir, name)
+                .and_then(|module| std::fs::canonicalize(module).ok())
+                .is_some_and(|module| built_for(&module, &version))
+        })
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// The version of the kernel `system` boots: the `lib/modules/<version>` its kernel package ships,
+/// or else the directory of the module tree depmod was run on, as that's only done for the kernel.
+pub fn kernel_version(system: &Path) -> Option<String> {
+    let only_dir = |dir: &Path, wanted: &dyn Fn(&Path) -> bool| {
+        std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(Result::ok)
+            .find(|entry| wanted(&entry.path()))?
+            .file_name()
+            .into_string()
+            .ok()
+    };
+    let package = std::fs::canonicalize(system.join("kernel"))
+        .ok()
+        .and_then(|kernel| Some(kernel.parent()?.join("lib/modules")));
+    package
+        .and_then(|modules| only_dir(&modules, &|path| path.is_dir()))
+        .or_else(|| {
+            only_dir(&system.join("kernel-modules/lib/modules"), &|path| {
+                path.join("modules.dep").exists()
+            })
+        })
+}
+
+/// Modules whose build differs between two systems.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Changed {
+    /// Told apart by what modinfo says.
+    pub versioned: Vec<String>,
+    /// Without anything modinfo could tell apart, so compared by their files instead.
+    pub unversioned: Vec<String>,
+}
+
+/// The most times `changed_modules` runs the `modinfo` program in one go; past that, modules are
+/// compared by their files.
+const MODINFO_RUNS: usize = 8;
+
+/// Modules out of `names` whose build differs between `booted` and `current`, going by their
+/// `.modinfo` section, or their files when it has nothing to go by. Compressed modules are read
+/// with the `modinfo` program if there is one (`None` for no exec). Modules missing from either
+/// system are left to `mismatched_modules`.
+pub fn changed_modules(
+    booted: &Path,
+    current: &Path,
+    names: &[&str],
+    modinfo: Option<&str>,
+) -> Result<Changed> {
+    let mut changed = Changed::default();
+    let mut runs_left = MODINFO_RUNS;
+
+    for name in names {
+        let (Some(old), Some(new)) = (locate(booted, name), locate(current, name)) else {
+            continue;
+        };
+        // the usual case, and one that needs no modinfo at all
+        if same_file(&old, &new) {
+            continue;
+        }
+        match (
+            module_identity(&old, modinfo, &mut runs_left),
+            module_identity(&new, modinfo, &mut runs_left),
+        ) {
+            (Some(old), Some(new)) if old != new => changed.versioned.push(name.to_string()),
+            (Some(_), Some(_)) => {}
+            _ => changed.unversioned.push(name.to_string()),
+        }
+    }
+
+    Ok(changed)
+}
+
+/// What tells this build of `module` apart, read from the file if it's uncompressed and with
+/// `modinfo` otherwise. `None` if neither works, e.g. with no modinfo installed.
+fn module_identity(module: &Path, modinfo: Option<&str>, runs_left: &mut usize) -> Option<String> {
+    let contents = std::fs::read(module).ok()?;
+    if let Some(info) = elf_modinfo(&contents) {
+        return identity(&info);
+    }
+    let modinfo = modinfo.filter(|_| *runs_left > 0)?;
+    *runs_left -= 1;
+    identity(&run_modinfo(modinfo, module).ok()?)
+}
+
+/// The `.modinfo` section of an uncompressed module, as `modinfo` prints it ("key: value" lines).
+/// `None` for anything that isn't an ELF file with one, like a compressed module.
+pub fn elf_modinfo(elf: &[u8]) -> Option<String> {
+    if elf.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let wide = match elf.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = match elf.get(5)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let int = |at: u64, size: usize| -> Option<u64> {
+        let at = usize::try_from(at).ok()?;
+        let bytes = elf.get(at..at.checked_add(size)?)?;
+        Some(bytes.iter().enumerate().fold(0, |int, (i, byte)| {
+            let shift = if big_endian { size - 1 - i } else { i } * 8;
+            int | u64::from(*byte) << shift
+        }))
+    };
+    let bytes = |offset: u64, size: u64| {
+        let start = usize::try_from(offset).ok()?;
+        elf.get(start..start.checked_add(usize::try_from(size).ok()?)?)
+    };
+
+    // where the section headers are, and which one has the section names
+    let word = if wide { 8 } else { 4 };
+    let (headers, header_size, count, names_index) = if wide {
+        (int(0x28, 8)?, int(0x3a, 2)?, int(0x3c, 2)?, int(0x3e, 2)?)
+    } else {
+        (int(0x20, 4)?, int(0x2e, 2)?, int(0x30, 2)?, int(0x32, 2)?)
+    };
+    // a section's name (as an offset into the names) and where its contents are
+    let section = |index: u64| -> Option<(u64, u64, u64)> {
+        let header = headers.checked_add(index.checked_mul(header_size)?)?;
+        let (offset_at, size_at) = if wide { (0x18, 0x20) } else { (0x10, 0x14) };
+        Some((
+            int(header, 4)?,
+            int(header.checked_add(offset_at)?, word)?,
+            int(header.checked_add(size_at)?, word)?,
+        ))
+    };
+
+    let (_, names_offset, names_size) = section(names_index)?;
+    let names = bytes(names_offset, names_size)?;
+    let (_, offset, size) = (0..count).filter_map(section).find(|(name, _, _)| {
+        usize::try_from(*name)
+            .ok()
+            .and_then(|name| names.get(name..))
+            .is_some_and(|name| name.starts_with(b".modinfo\0"))
+    })?;
+    Some(
+        bytes(offset, size)?
+            .split(|byte| *byte == 0)
+            .filter_map(|entry| {
+                let (key, value) = std::str::from_utf8(entry).ok()?.split_once('=')?;
+                Some(format!("{key}: {value}\n"))
+            })
+            .collect(),
+    )
+}
+
+/// `finding` with its items out of `modules` (and generic "module changed" ones) replaced by a
+/// single "N kernel modules changed" once there are more than `limit` of them, so a mass rebuild
+/// doesn't read as a wall of module names.
+pub fn collapse(finding: &Finding, modules: &[&str], limit: usize) -> Finding {
+    let is_module = |item: &String| item == "module changed" || modules.contains(&item.as_str());
+    let count = finding.items.iter().filter(|item| is_module(item)).count();
+    if count <= limit {
+        return finding.clone();
+    }
+
+    let mut items = finding
+        .items
+        .iter()
+        .filter(|item| !is_module(item))
+        .cloned()
+        .collect::<Vec<_>>();
+    items.push(format!("{count} kernel modules changed"));
+    Finding {
+        label: finding.label.clone(),
+        items,
+    }
+}
+
+/// Whether two module files are the same store path, or at least have the same contents.
+fn same_file(a: &Path, b: &Path) -> bool {
+    let (Ok(a), Ok(b)) = (std::fs::canonicalize(a), std::fs::canonicalize(b)) else {
+        return false;
+    };
+    a == b
+        || std::fs::read(a)
+            .ok()
+            .zip(std::fs::read(b).ok())
+            .is_some_and(|(a, b)| a == b)
+}
+
+/// What tells builds of a module apart in `modinfo` output: its version, or when that's missing or
+/// a placeholder like "#VERSION#" (as some out-of-tree modules ship), its srcversion, and failing
+/// that its vermagic.
+pub fn identity(modinfo: &str) -> Option<String> {
+    let field = |name: &str| {
+        modinfo.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.trim();
+            (key.trim() == name && !value.is_empty() && !value.contains('#'))
+                .then(|| format!("{name} {value}"))
+        })
+    };
+    field("version")
+        .or_else(|| field("srcversion"))
+        .or_else(|| field("vermagic"))
+}
+
+fn locate(system: &Path, name: &str) -> Option<PathBuf> {
+    let version = kernel_version(system)?;
+    find_module(
+        &system.join("kernel-modules/lib/modules").join(version),
+        name,
+    )
+}
+
+fn run_modinfo(modinfo: &str, module: &Path) -> Result<String> {
+    let output = std::process::Command::new(modinfo)
+        .arg(module)
+        .output()
+        .tool_context(|| format!("Could not run {modinfo}"))?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "{modinfo} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether a resolved module path lives under `lib/modules/<version>` of whatever built it.
+fn built_for(module: &Path, kernel_version: &str) -> bool {
+    module
+        .to_string_lossy()
+        .contains(&format!("/lib/modules/{kernel_version}/"))
+}
+
+/// Look for `name.ko`, possibly compressed, anywhere under `dir`.
+fn find_module(dir: &Path, name: &str) -> Option<PathBuf> {
+    for entry in std::fs::read_dir(dir).ok()?.filter_map(Result::ok) {
+        let path = entry.path();
+        // the module tree is a forest of symlinks into the store, so follow them
+        if path.is_dir() {
+            if let Some(found) = find_module(&path, name) {
+                return Some(found);
+            }
+        } else if entry
+            .file_name()
+            .to_string_lossy()
+            .split_once(".ko")
+            .is_some_and(|(stem, _)| stem == name)
+        {
+            return Some(path);
+        }
+    }
+    None
+}
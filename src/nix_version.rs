@@ -0,0 +1,63 @@
+// an old nix misses fixes (and sometimes security ones), and after upgrading it the daemon keeps
+// running the old binary until it's restarted, so client and daemon quietly disagree.
+
+use crate::error::{IoContext, Result};
+use std::path::{Path, PathBuf};
+
+/// Version of the nix a store path belongs to, e.g. "2.18.1" for
+/// `/nix/store/<hash>-nix-2.18.1/bin/nix`. Works for forks named differently, like lix.
+pub fn version_of(path: &Path) -> Option<String> {
+    let package = path
+        .strip_prefix("/nix/store")
+        .ok()?
+        .components()
+        .next()?
+        .as_os_str()
+        .to_str()?;
+    let (_hash, name) = package.split_once('-')?;
+    // the version starts at the first dash followed by a digit
+    let at = name
+        .match_indices('-')
+        .find(|(at, _)| name[at + 1..].starts_with(|c: char| c.is_ascii_digit()))?
+        .0;
+    Some(name[at + 1..].to_string())
+}
+
+/// Whether `version` is older than `floor`, comparing the numeric parts of each, so "2.9" is
+/// older than "2.18" and any "pre" suffix is ignored.
+pub fn older_than(version: &str, floor: &str) -> bool {
+    let numbers = |version: &str| {
+        version
+            .split(['.', '-'])
+            .map_while(|part| part.parse::<u64>().ok())
+            .collect::<Vec<_>>()
+    };
+    numbers(version) < numbers(floor)
+}
+
+/// The nix binary the system profile at `system` provides.
+pub fn installed(system: &Path) -> Result<PathBuf> {
+    let nix = system.join("sw/bin/nix");
+    std::fs::canonicalize(&nix).system_context(|| format!("Could not resolve {}", nix.display()))
+}
+
+/// The binary the running nix-daemon was started from, found under `proc` (normally `/proc`).
+/// Only root can see it, so this is `None` for anyone else as well as when there's no daemon.
+pub fn daemon_binary(proc: &Path) -> Result<Option<PathBuf>> {
+    for entry in std::fs::read_dir(proc)
+        .system_context(|| format!("Could not read {}", proc.display()))?
+        .filter_map(Result::ok)
+    {
+        let Ok(comm) = std::fs::read_to_string(entry.path().join("comm")) else {
+            continue;
+        };
+        if comm.trim() != "nix-daemon" {
+            continue;
+        }
+        // the daemon forks a child per connection, any of them will do
+        if let Ok(exe) = std::fs::read_link(entry.path().join("exe")) {
+            return Ok(Some(exe));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,185 @@
+// sending how long each check took to an OpenTelemetry collector, to see checks slowing down across
+// a fleet next to everything else there. OTLP over HTTP with JSON needs nothing but a socket, so
+// only plain http is spoken; put a local collector in front for anything fancier.
+
+use crate::error::{Error, IoContext, Result};
+use crate::Span;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+/// How long an export gets altogether, looking up the collector included, so a dead one doesn't
+/// pile up exports behind it.
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The identifiers tying a run's spans together.
+pub struct Ids {
+    /// 32 hex digits.
+    pub trace: String,
+    /// 16 hex digits each, one for the run and one for each span.
+    pub root: String,
+    pub spans: Vec<String>,
+}
+
+impl Ids {
+    /// Fresh random identifiers for `count` spans.
+    pub fn random(count: usize) -> Ids {
+        let hex = || {
+            use std::hash::{BuildHasher, Hasher};
+            // RandomState is seeded randomly for each instance, no need for a crate just for this
+            let random = std::collections::hash_map::RandomState::new()
+                .build_hasher()
+                .finish();
+            format!("{random:016x}")
+        };
+        Ids {
+            trace: format!("{}{}", hex(), hex()),
+            root: hex(),
+            spans: (0..count).map(|_| hex()).collect(),
+        }
+    }
+}
+
+/// An OTLP JSON trace export of `spans` from `host`, each a child of one span for the whole run.
+pub fn body(spans: &[Span], host: &str, ids: &Ids) -> serde_json::Value {
+    let attribute =
+        |key: &str, value: &str| serde_json::json!({"key": key, "value": {"stringValue": value}});
+    let span = |name: &str, id: &str, parent: Option<&str>, start: u128, end: u128| {
+        let mut span = serde_json::json!({
+            "traceId": ids.trace,
+            "spanId": id,
+            "name": name,
+            // internal
+            "kind": 1,
+            // 64 bit integers are strings in OTLP's JSON
+            "startTimeUnixNano": start.to_string(),
+            "endTimeUnixNano": end.to_string(),
+        });
+        if let Some(parent) = parent {
+            span["parentSpanId"] = parent.into();
+        }
+        span
+    };
+
+    let start = spans.iter().map(|span| span.start).min().unwrap_or(0);
+    let end = spans
+        .iter()
+        .map(|span| span.start + span.duration.as_nanos())
+        .max()
+        .unwrap_or(start);
+    let mut exported = vec![span("checks", &ids.root, None, start, end)];
+    for (check, id) in spans.iter().zip(&ids.spans) {
+        let mut exported_span = span(
+            &check.check,
+            id,
+            Some(&ids.root),
+            check.start,
+            check.start + check.duration.as_nanos(),
+        );
+        exported_span["status"] = match &check.error {
+            Some(error) => serde_json::json!({"code": 2, "message": error}),
+            None => serde_json::json!({"code": 1}),
+        };
+        exported.push(exported_span);
+    }
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", "i3status-nix-update-widget"),
+                    attribute("service.version", env!("CARGO_PKG_VERSION")),
+                    attribute("host.name", host),
+                ],
+            },
+            "scopeSpans": [{
+                "scope": {"name": "i3status-nix-update-widget"},
+                "spans": exported,
+            }],
+        }],
+    })
+}
+
+/// POST `body` to the collector at `endpoint` (like `http://collector:4318`) as its traces, giving
+/// up once `timeout` has passed since starting.
+pub fn export(endpoint: &str, body: &serde_json::Value, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    let context = || format!("Could not send traces to {endpoint}");
+    // a timeout of zero means none at all to the socket, so running out is checked here
+    let left = || {
+        Some(deadline.saturating_duration_since(Instant::now()))
+            .filter(|left| !left.is_zero())
+            .ok_or_else(|| Error::Network {
+                context: format!("{endpoint} took longer than {}s", timeout.as_secs_f32()),
+                source: None,
+            })
+    };
+    let authority = endpoint
+        .strip_prefix("http://")
+        .ok_or_else(|| Error::Config(format!("OTLP endpoint {endpoint} isn't http://")))?;
+    let (authority, base) = match authority.split_once('/') {
+        Some((authority, base)) => (authority, format!("/{}", base.trim_end_matches('/'))),
+        None => (authority, String::new()),
+    };
+
+    // the resolver has no timeout of its own, so it's left behind if it takes too long
+    let (resolved, resolving) = std::sync::mpsc::channel();
+    let lookup = authority.to_string();
+    std::thread::spawn(move || {
+        let _ = resolved.send(std::net::ToSocketAddrs::to_socket_addrs(&lookup));
+    });
+    let address = resolving
+        .recv_timeout(left()?)
+        .map_err(|_| {
+            left().err().unwrap_or_else(|| Error::Network {
+                context: format!("Could not look up {authority}"),
+                source: None,
+            })
+        })?
+        .network_context(context)?
+        .next()
+        .ok_or_else(|| Error::Network {
+            context: format!("OTLP endpoint {endpoint} has no address"),
+            source: None,
+        })?;
+
+    let mut stream =
+        std::net::TcpStream::connect_timeout(&address, left()?).network_context(context)?;
+    stream
+        .set_write_timeout(Some(left()?))
+        .network_context(context)?;
+    let body = body.to_string();
+    let request = format!(
+        "POST {base}/v1/traces HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .network_context(context)?;
+
+    // only the status line matters, read until it's in or the time is up
+    let mut response = Vec::new();
+    let mut buffer = [0; 512];
+    while !response.contains(&b'\n') {
+        stream
+            .set_read_timeout(Some(left()?))
+            .network_context(context)?;
+        let read = stream.read(&mut buffer).network_context(context)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    if !status
+        .split_whitespace()
+        .nth(1)
+        .is_some_and(|code| code.starts_with('2'))
+    {
+        return Err(Error::Network {
+            context: format!("{endpoint} failed: {status}"),
+            source: None,
+        });
+    }
+    Ok(())
+}
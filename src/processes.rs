@@ -0,0 +1,63 @@
+// a process still mapping files from a store path that has since been garbage collected is running
+// code that no longer exists on disk. that's the strongest hint that something needs restarting.
+
+use crate::error::{IoContext, Result};
+use std::path::Path;
+
+/// Whether a `/proc/<pid>/maps` listing includes a deleted file from the nix store.
+pub fn maps_deleted_store_path(maps: &str) -> bool {
+    maps.lines().any(|line| {
+        line.split_once("/nix/store/")
+            .is_some_and(|(_, path)| path.ends_with(" (deleted)"))
+    })
+}
+
+/// Names of the processes under `proc` (normally `/proc`) running from deleted store paths, sorted
+/// and deduplicated. Processes we aren't allowed to look at are skipped.
+pub fn processes_using_deleted_store_paths(proc: &Path) -> Result<Vec<String>> {
+    scan(proc, |_| true)
+}
+
+/// The same, for only the processes `uid` owns, i.e. the ones logging out and back in restarts.
+pub fn owned_processes_using_deleted_store_paths(proc: &Path, uid: u32) -> Result<Vec<String>> {
+    use std::os::unix::fs::MetadataExt;
+    scan(proc, |dir| {
+        dir.metadata().is_ok_and(|meta| meta.uid() == uid)
+    })
+}
+
+fn scan(proc: &Path, wanted: impl Fn(&Path) -> bool) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    for entry in std::fs::read_dir(proc)
+        .system_context(|| format!("Could not read {}", proc.display()))?
+        .filter_map(Result::ok)
+    {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
+            continue;
+        }
+        if !wanted(&entry.path()) {
+            continue;
+        }
+
+        // processes come and go, and other users' maps aren't readable
+        let Ok(maps) = std::fs::read_to_string(entry.path().join("maps")) else {
+            continue;
+        };
+        if !maps_deleted_store_path(&maps) {
+            continue;
+        }
+        if let Ok(comm) = std::fs::read_to_string(entry.path().join("comm")) {
+            names.push(comm.trim().to_string());
+        }
+    }
+
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
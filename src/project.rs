@@ -0,0 +1,34 @@
+// a status line embedded in a terminal (tmux, a prompt) runs in some project's directory. if that
+// project has a flake.lock, an ancient nixpkgs pinned in it is worth pointing out too.
+
+use crate::error::{Error, IoContext, Result};
+use std::path::{Path, PathBuf};
+
+/// The project the status line is running for: the direnv one if there is one, otherwise `pwd`.
+/// direnv sets `DIRENV_DIR` to the directory with a "-" in front.
+pub fn project_dir(direnv_dir: Option<&str>, pwd: &Path) -> PathBuf {
+    direnv_dir
+        .and_then(|dir| dir.strip_prefix('-'))
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(|| pwd.to_path_buf(), PathBuf::from)
+}
+
+/// When the nixpkgs the flake.lock in `dir` pins was last modified, if there's a lock that pins one.
+pub fn pinned_nixpkgs(dir: &Path) -> Result<Option<i64>> {
+    let path = dir.join("flake.lock");
+    let lock = match std::fs::read_to_string(&path) {
+        Ok(lock) => lock,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).system_context(|| format!("Could not read {}", path.display()))
+        }
+    };
+    let lock: serde_json::Value = serde_json::from_str(&lock)
+        .map_err(|_| Error::FlakeLock(format!("{} is not valid JSON", path.display())))?;
+
+    // the root's nixpkgs input names the node, which is usually but not always called nixpkgs
+    let node = lock["nodes"]["root"]["inputs"]["nixpkgs"]
+        .as_str()
+        .unwrap_or("nixpkgs");
+    Ok(lock["nodes"][node]["locked"]["lastModified"].as_i64())
+}
@@ -0,0 +1,29 @@
+// the protocol between the privileged half (a root service running `publish` on a timer) and the
+// unprivileged bar, which only ever reads what was published.
+
+use crate::state_dir::Stored;
+use crate::Finding;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct Published {
+    /// Unix timestamp of the run that found these.
+    pub timestamp: i64,
+    pub findings: Vec<Finding>,
+}
+
+impl Published {
+    /// A finding saying these are older than `max_hours` at `now`, so they may no longer be true:
+    /// the service publishing them has stopped, or its timer has.
+    pub fn stale(&self, now: i64, max_hours: i64) -> Option<Finding> {
+        let hours = (now - self.timestamp) / 3600;
+        (hours >= max_hours).then(|| Finding {
+            label: "Published stale".to_string(),
+            items: vec![format!("{hours}h old")],
+        })
+    }
+}
+
+impl Stored for Published {
+    const NAME: &'static str = "published.json";
+    const VERSION: u32 = 1;
+}
@@ -0,0 +1,21 @@
+// py3status (and i3pystatus) modules hand back a composite: a list of parts, each with its own
+// text and colour. we only ever need the one part.
+
+use crate::theme::Theme;
+use crate::{BarCommand, State};
+
+#[derive(serde::Serialize, Debug, PartialEq, Eq)]
+pub struct Part {
+    pub full_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<&'static str>,
+    pub urgent: bool,
+}
+
+pub fn composite(command: &BarCommand, theme: &Theme) -> Vec<Part> {
+    vec![Part {
+        full_text: command.text.clone(),
+        color: theme.color(command.state),
+        urgent: command.state == State::Critical,
+    }]
+}
@@ -0,0 +1,191 @@
+// compares the system that was booted with the one that's been switched to.
+// anything that only takes effect on boot and differs between the two needs a reboot.
+
+use crate::error::{Error, IoContext, Result};
+use std::path::Path;
+
+pub const BOOTED_SYSTEM: &str = "/run/booted-system";
+pub const CURRENT_SYSTEM: &str = "/run/current-system";
+
+/// Parts of a system closure that are only picked up at boot.
+const BOOT_COMPONENTS: [&str; 2] = ["kernel", "initrd"];
+
+/// Names of the boot components whose store paths differ between `booted` and `current`.
+pub fn changed_boot_components(booted: &Path, current: &Path) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+
+    for name in BOOT_COMPONENTS {
+        if resolve(&booted.join(name))? != resolve(&current.join(name))? {
+            changed.push(name.to_string());
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Something site-specific that needs a reboot when it changes, compared on top of the kernel and
+/// initrd.
+pub enum Probe {
+    /// Two paths, compared by what they resolve to. A path that doesn't exist resolves to nothing.
+    Paths {
+        name: &'static str,
+        booted: &'static str,
+        current: &'static str,
+    },
+    /// A program run with each system as its argument, compared by what it prints.
+    Command {
+        name: &'static str,
+        command: &'static str,
+    },
+}
+
+/// Names of the `probes` that differ between `booted` and `current`. `no_exec` skips the ones that
+/// would have to run something.
+pub fn changed_probes(
+    probes: &[Probe],
+    booted: &Path,
+    current: &Path,
+    no_exec: bool,
+) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+
+    for probe in probes {
+        let (name, differs) = match probe {
+            Probe::Paths {
+                name,
+                booted,
+                current,
+            } => (
+                name,
+                std::fs::canonicalize(booted).ok() != std::fs::canonicalize(current).ok(),
+            ),
+            Probe::Command { .. } if no_exec => continue,
+            Probe::Command { name, command } => (
+                name,
+                run_probe(command, booted)? != run_probe(command, current)?,
+            ),
+        };
+        if differs {
+            changed.push(name.to_string());
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Where a system closure keeps its systemd units.
+const UNIT_DIR: &str = "etc/systemd/system";
+
+/// Units that `current` has and `booted` doesn't as "+name", and the other way around as "-name".
+/// Only a reboot (or a switch that got that far) starts and stops those.
+pub fn changed_units(booted: &Path, current: &Path) -> Result<Vec<String>> {
+    let booted = units(&booted.join(UNIT_DIR))?;
+    let current = units(&current.join(UNIT_DIR))?;
+
+    let added = current.difference(&booted).map(|unit| format!("+{unit}"));
+    let removed = booted.difference(&current).map(|unit| format!("-{unit}"));
+    Ok(added.chain(removed).collect())
+}
+
+/// Names of the unit files in `dir`, leaving out the .wants/.requires/.d directories. Nothing if
+/// it doesn't exist.
+fn units(dir: &Path) -> Result<std::collections::BTreeSet<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Default::default()),
+        Err(err) => return Err(err).system_context(|| format!("Could not read {}", dir.display())),
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| !name.ends_with(".wants") && !name.ends_with(".requires"))
+        .filter(|name| !name.ends_with(".d"))
+        .collect())
+}
+
+fn run_probe(command: &str, system: &Path) -> Result<Vec<u8>> {
+    let output = std::process::Command::new(command)
+        .arg(system)
+        .output()
+        .tool_context(|| format!("Could not run {command}"))?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "{command} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(output.stdout)
+}
+
+fn resolve(path: &Path) -> Result<std::path::PathBuf> {
+    std::fs::canonicalize(path).system_context(|| format!("Could not resolve {}", path.display()))
+}
+
+/// Where the Debian-style reboot-required markers go.
+pub const REBOOT_REQUIRED_DIR: &str = "/run";
+
+/// Create `reboot-required` and `reboot-required.pkgs` in `dir` listing `changed`, or remove them
+/// once nothing needs a reboot any more.
+pub fn sync_reboot_required(dir: &Path, changed: &[String]) -> Result<()> {
+    let marker = dir.join("reboot-required");
+    let pkgs = dir.join("reboot-required.pkgs");
+
+    if changed.is_empty() {
+        for file in [marker, pkgs] {
+            match std::fs::remove_file(&file) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(err)
+                        .state_context(|| format!("Could not remove {}", file.display()));
+                }
+                _ => {}
+            }
+        }
+        return Ok(());
+    }
+
+    let pkgs_contents: String = changed.iter().map(|name| format!("{name}\n")).collect();
+    crate::state_dir::write_atomically(&pkgs, &pkgs_contents)?;
+    crate::state_dir::write_atomically(&marker, "*** System restart required ***\n")
+}
+
+/// What other tooling has marked as needing a reboot in `dir`: the contents of
+/// `reboot-required.pkgs`, failing that the reasons nixos-needsreboot writes into the marker itself,
+/// or just "system" if there's a marker with neither.
+pub fn read_reboot_required(dir: &Path) -> Result<Vec<String>> {
+    let Some(marker) = read_lines(&dir.join("reboot-required"))? else {
+        return Ok(vec![]);
+    };
+
+    let pkgs = read_lines(&dir.join("reboot-required.pkgs"))?.unwrap_or_default();
+    if !pkgs.is_empty() {
+        return Ok(pkgs);
+    }
+
+    let reasons: Vec<_> = marker
+        .into_iter()
+        .filter(|line| !line.starts_with("***"))
+        .collect();
+    if !reasons.is_empty() {
+        return Ok(reasons);
+    }
+
+    Ok(vec!["system".to_string()])
+}
+
+/// Non-empty lines of `path`, or `None` if it doesn't exist.
+fn read_lines(path: &Path) -> Result<Option<Vec<String>>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).system_context(|| format!("Could not read {}", path.display())),
+    }
+}
@@ -0,0 +1,22 @@
+// a reboot that's been pending for weeks deserves more nagging than one from this morning, so when
+// it first became needed is remembered across runs.
+
+use crate::state_dir::Stored;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RebootPending {
+    /// Unix timestamp of when a reboot was first seen to be needed, `None` while none is.
+    pub since: Option<i64>,
+}
+
+impl Stored for RebootPending {
+    const NAME: &'static str = "reboot_pending.json";
+    const VERSION: u32 = 1;
+}
+
+/// `previous` brought up to date with whether a reboot is `needed` at `now`.
+pub fn advance(previous: Option<&RebootPending>, needed: bool, now: i64) -> RebootPending {
+    RebootPending {
+        since: needed.then(|| previous.and_then(|previous| previous.since).unwrap_or(now)),
+    }
+}
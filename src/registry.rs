@@ -0,0 +1,44 @@
+// `nix registry pin` fixes an entry to one revision, and it stays there until someone remembers it.
+// every `nix run nixpkgs#...` then quietly uses that old nixpkgs.
+
+use crate::error::{Error, IoContext, Result};
+use std::path::Path;
+
+pub const SYSTEM_REGISTRY: &str = "/etc/nix/registry.json";
+
+/// The user registry, under `$XDG_CONFIG_HOME` or `~/.config`.
+pub fn user_registry() -> Option<std::path::PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("nix/registry.json"))
+}
+
+/// The pinned entries in the registry at `path` and when what they're pinned to was last
+/// modified. Entries that aren't pinned to a revision don't have a date and are skipped.
+pub fn pins(path: &Path) -> Result<Vec<(String, i64)>> {
+    let registry = match std::fs::read_to_string(path) {
+        Ok(registry) => registry,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).system_context(|| format!("Could not read {}", path.display()))
+        }
+    };
+    let registry: serde_json::Value =
+        serde_json::from_str(&registry).map_err(|_| Error::SystemProfile {
+            context: format!("{} is not valid JSON", path.display()),
+            source: None,
+        })?;
+
+    Ok(registry["flakes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some((
+                entry["from"]["id"].as_str()?.to_string(),
+                entry["to"]["lastModified"].as_i64()?,
+            ))
+        })
+        .collect())
+}
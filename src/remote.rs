@@ -0,0 +1,142 @@
+// checking other hosts over ssh, so one machine can keep an eye on a few servers. hosts can be
+// named one by one, by a glob over the Host entries of ~/.ssh/config, or by a file listing them.
+
+use crate::agent;
+use crate::aggregate::{self, HostStatus};
+use crate::rules::glob_match;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// The concrete host names in an ssh config, leaving out patterns like `*.internal` or `!bastion`.
+pub fn ssh_config_hosts(config: &str) -> Vec<String> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            words
+                .next()
+                .is_some_and(|keyword| keyword.eq_ignore_ascii_case("host"))
+                .then_some(words)
+        })
+        .flatten()
+        .filter(|host| !host.contains(['*', '?', '!']))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Hosts for each of `specs`: `@path` is a file with a host per line (# starts a comment), a glob
+/// picks out of `known` (the ssh config's hosts), and anything else is a host as is. Duplicates
+/// are dropped.
+pub fn expand(
+    specs: &[String],
+    known: &[String],
+    read_file: impl Fn(&str) -> std::io::Result<String>,
+) -> std::io::Result<Vec<String>> {
+    let mut hosts: Vec<String> = Vec::new();
+    for spec in specs {
+        let expanded = if let Some(path) = spec.strip_prefix('@') {
+            read_file(path)?
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else if spec.contains(['*', '?']) {
+            known
+                .iter()
+                .filter(|host| glob_match(spec, host))
+                .cloned()
+                .collect()
+        } else {
+            vec![spec.clone()]
+        };
+        for host in expanded {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+    }
+    Ok(hosts)
+}
+
+/// Ask an agent for its status with `command` for each of `hosts` (with `{host}` in it replaced),
+/// at most `parallel` at a time, each killed after `timeout`. Returned in the order of `hosts`.
+pub fn check_all(
+    hosts: &[String],
+    command: &[&str],
+    parallel: usize,
+    timeout: Duration,
+) -> Vec<HostStatus> {
+    let next = std::sync::Mutex::new(hosts.iter().enumerate());
+    let results = std::sync::Mutex::new(vec![None; hosts.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.clamp(1, hosts.len().max(1)) {
+            scope.spawn(|| loop {
+                let Some((at, host)) = next.lock().unwrap().next() else {
+                    return;
+                };
+                let output = run(command, host, timeout).and_then(|output| agent::status(&output));
+                let status = aggregate::parse(host.clone(), output);
+                results.lock().unwrap()[at] = Some(status);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// What `command` prints for `host` when asked for its status, or why it didn't.
+fn run(command: &[&str], host: &str, timeout: Duration) -> std::result::Result<String, String> {
+    // ssh would take it for an option, like -oProxyCommand=...
+    if host.starts_with('-') {
+        return Err(format!("{host} isn't a host name"));
+    }
+    let (program, args) = command.split_first().ok_or("no command")?;
+    let mut child = std::process::Command::new(program)
+        .args(
+            args.iter()
+                .map(|arg| if *arg == "{host}" { host } else { arg }),
+        )
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|err| format!("Could not run {program}: {err}"))?;
+
+    // one request, then end of input so the agent exits once it has answered
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let _ = writeln!(stdin, "{}", agent::request());
+    drop(stdin);
+
+    // read in the background so a chatty command can't fill the pipe and stall
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        std::io::Read::read_to_string(&mut stdout, &mut output).map(|_| output)
+    });
+
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break,
+            Ok(Some(status)) => return Err(format!("{program} failed: {status}")),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    reader
+        .join()
+        .map_err(|_| "reader panicked".to_string())?
+        .map_err(|err| err.to_string())
+}
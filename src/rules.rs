@@ -0,0 +1,86 @@
+// module sets differ from machine to machine, so rather than a flat ignore list, findings can be
+// matched by glob and ignored or given their own severity.
+
+use crate::{Finding, State};
+
+/// What to do with the finding items a glob matches.
+pub struct Rule {
+    /// `*` matches any run of characters, `?` any one character.
+    pub pattern: &'static str,
+    /// The state the item counts as, or `None` to drop it.
+    pub state: Option<State>,
+}
+
+/// Whether `text` matches the whole of `pattern`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    // where to resume after the last * if the rest doesn't match
+    let (mut p, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some('?') => (p, t) = (p + 1, t + 1),
+            Some(c) if *c == text[t] => (p, t) = (p + 1, t + 1),
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    (p, t) = (star_p + 1, star_t + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The first of `rules` that matches `item`.
+fn rule_for<'a>(rules: &'a [Rule], item: &str) -> Option<&'a Rule> {
+    rules.iter().find(|rule| glob_match(rule.pattern, item))
+}
+
+/// `findings` without the items a rule drops, and without findings that are left with none.
+pub fn apply(findings: Vec<Finding>, rules: &[Rule]) -> Vec<Finding> {
+    findings
+        .into_iter()
+        .filter_map(|mut finding| {
+            finding
+                .items
+                .retain(|item| rule_for(rules, item).is_none_or(|rule| rule.state.is_some()));
+            (!finding.items.is_empty()).then_some(finding)
+        })
+        .collect()
+}
+
+/// `findings` without the items that count as less severe than `min_severity`, and without
+/// findings that are left with none, so what isn't shown can't raise the block's state either.
+pub fn at_least(findings: Vec<Finding>, rules: &[Rule], min_severity: State) -> Vec<Finding> {
+    findings
+        .into_iter()
+        .filter_map(|mut finding| {
+            finding.items.retain(|item| {
+                let state = rule_for(rules, item).and_then(|rule| rule.state);
+                state.unwrap_or(State::Warning) >= min_severity
+            });
+            (!finding.items.is_empty()).then_some(finding)
+        })
+        .collect()
+}
+
+/// The state `finding` counts as: the worst of its items, which are Warning unless a rule says
+/// otherwise.
+pub fn state(finding: &Finding, rules: &[Rule]) -> State {
+    finding
+        .items
+        .iter()
+        .map(|item| {
+            rule_for(rules, item)
+                .and_then(|rule| rule.state)
+                .unwrap_or(State::Warning)
+        })
+        .max()
+        .unwrap_or(State::Warning)
+}
@@ -0,0 +1,132 @@
+// lanzaboote signs the boot stubs it installs. if signing failed, the next boot under Secure Boot
+// won't get past the firmware, so it's worth knowing before rebooting.
+
+use crate::error::{Error, IoContext, Result};
+use std::io::Read;
+use std::path::Path;
+
+/// Files `sbctl verify` reported as not signed.
+pub fn parse_sbctl_verify(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('✗'))
+        .map(|rest| {
+            let rest = rest.trim();
+            rest.strip_suffix("is not signed")
+                .unwrap_or(rest)
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Run `sbctl verify` and return the files on the ESP that aren't signed.
+pub fn unsigned_boot_files(sbctl: &str) -> Result<Vec<String>> {
+    let output = std::process::Command::new(sbctl)
+        .arg("verify")
+        .output()
+        .tool_context(|| format!("Could not run {sbctl}"))?;
+
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "sbctl verify failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+
+    Ok(parse_sbctl_verify(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Where lanzaboote and systemd-boot keep their EFI binaries.
+pub const ESP_EFI_DIR: &str = "/boot/EFI";
+
+/// Whether a PE image carries an Authenticode signature, or `None` if it isn't a PE image. This
+/// only looks for a certificate table, it doesn't check the signature against the enrolled keys.
+pub fn pe_is_signed(image: &[u8]) -> Option<bool> {
+    let u16_at = |offset: usize| -> Option<u16> {
+        Some(u16::from_le_bytes(
+            image.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            image.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    if image.get(..2)? != b"MZ" {
+        return None;
+    }
+    let pe = u32_at(0x3C)? as usize;
+    if image.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    // the optional header follows the 4 byte signature and the 20 byte COFF header
+    let optional = pe + 24;
+    let (rva_count, directories) = match u16_at(optional)? {
+        0x10B => (u32_at(optional + 92)?, optional + 96),
+        0x20B => (u32_at(optional + 108)?, optional + 112),
+        _ => return None,
+    };
+
+    // the certificate table is the fifth data directory
+    const CERTIFICATE_TABLE: usize = 4;
+    if (rva_count as usize) <= CERTIFICATE_TABLE {
+        return Some(false);
+    }
+    let size = u32_at(directories + CERTIFICATE_TABLE * 8 + 4)?;
+    Some(size > 0)
+}
+
+/// EFI binaries under `dir` without a signature, found without running sbctl.
+pub fn unsigned_efi_files(dir: &Path) -> Result<Vec<String>> {
+    let mut unsigned = Vec::new();
+
+    for entry in std::fs::read_dir(dir)
+        .system_context(|| format!("Could not read {}", dir.display()))?
+        .filter_map(Result::ok)
+    {
+        let path = entry.path();
+        if path.is_dir() {
+            unsigned.extend(unsigned_efi_files(&path)?);
+            continue;
+        }
+        if !path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("efi"))
+        {
+            continue;
+        }
+
+        // the headers are all we need, and they're right at the start
+        let mut header = Vec::new();
+        std::fs::File::open(&path)
+            .and_then(|file| file.take(4096).read_to_end(&mut header))
+            .system_context(|| format!("Could not read {}", path.display()))?;
+
+        if pe_is_signed(&header) != Some(true) {
+            unsigned.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    unsigned.sort();
+    Ok(unsigned)
+}
+
+/// The unsigned files on the ESP, asking `sbctl` (`None` for no exec) if it's installed, or
+/// looking for the signatures in `esp` ourselves if it isn't, e.g. on another distro.
+pub fn unsigned_files(sbctl: Option<&str>, esp: &Path) -> Result<Vec<String>> {
+    let Some(sbctl) = sbctl else {
+        return unsigned_efi_files(esp);
+    };
+    match unsigned_boot_files(sbctl) {
+        Err(Error::ExternalTool {
+            source: Some(err), ..
+        }) if err.kind() == std::io::ErrorKind::NotFound => unsigned_efi_files(esp),
+        unsigned => unsigned,
+    }
+}
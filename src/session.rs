@@ -0,0 +1,40 @@
+// a laptop spends most of the day locked, and nobody reads the bar then. logind knows whether the
+// session is idle (swayidle's idlehint sets that) or locked (if the locker tells it).
+
+use crate::error::{Error, IoContext, Result};
+
+/// Whether `loginctl show-session` output with the IdleHint and LockedHint properties says the
+/// session is idle or locked.
+pub fn parse_hints(output: &str) -> bool {
+    output.lines().any(|line| {
+        matches!(
+            line.trim().split_once('='),
+            Some(("IdleHint" | "LockedHint", "yes"))
+        )
+    })
+}
+
+/// Whether the session we run in is idle or locked, by asking loginctl.
+pub fn idle() -> Result<bool> {
+    // XDG_SESSION_ID is missing when started outside the session, e.g. from a user service
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let output = std::process::Command::new("loginctl")
+        .args([
+            "show-session",
+            &session,
+            "--property=IdleHint",
+            "--property=LockedHint",
+        ])
+        .output()
+        .tool_context(|| "Could not run loginctl".to_string())?;
+    if !output.status.success() {
+        return Err(Error::ExternalTool {
+            context: format!(
+                "loginctl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            source: None,
+        });
+    }
+    Ok(parse_hints(&String::from_utf8_lossy(&output.stdout)))
+}
@@ -0,0 +1,132 @@
+// one place for everything the widget keeps on disk between runs. writes are atomic and made
+// under a lock, and every file carries a format version so an upgrade never misreads an old one.
+
+use crate::error::{IoContext, Result};
+use std::path::{Path, PathBuf};
+
+const APP: &str = "i3status-nix-update-widget";
+
+/// Something kept in the state directory.
+pub trait Stored: serde::Serialize + serde::de::DeserializeOwned {
+    /// File name within the state directory.
+    const NAME: &'static str;
+    /// Bumped whenever the format changes incompatibly. Files with another version are ignored.
+    const VERSION: u32;
+}
+
+#[derive(serde::Serialize)]
+struct Envelope<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(serde::Deserialize)]
+struct OwnedEnvelope<T> {
+    version: u32,
+    data: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDir {
+    pub path: PathBuf,
+    /// Never write anything, not even the lock file, e.g. on a live ISO.
+    pub read_only: bool,
+}
+
+impl StateDir {
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        StateDir {
+            path: path.into(),
+            read_only: false,
+        }
+    }
+
+    /// The same directory, but every write fails instead of touching the disk.
+    pub fn read_only(self) -> Self {
+        StateDir {
+            read_only: true,
+            ..self
+        }
+    }
+
+    /// Shared by the whole machine, written by root.
+    pub fn system() -> Self {
+        StateDir::at(Path::new("/var/lib").join(APP))
+    }
+
+    /// Per-user, following the XDG base directory spec.
+    pub fn user() -> Option<Self> {
+        let base = match std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+        };
+        Some(StateDir::at(base.join(APP)))
+    }
+
+    /// The directory the build configured, if it did, otherwise [`StateDir::system`].
+    pub fn system_or(configured: Option<&str>) -> Self {
+        configured.map_or_else(StateDir::system, StateDir::at)
+    }
+
+    /// The directory the build configured, if it did, otherwise [`StateDir::user`].
+    pub fn user_or(configured: Option<&str>) -> Option<Self> {
+        configured.map(StateDir::at).or_else(StateDir::user)
+    }
+
+    fn lock_file(&self) -> Result<std::fs::File> {
+        let path = self.path.join(".lock");
+        std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .state_context(|| format!("Could not open {}", path.display()))
+    }
+
+    pub fn write<T: Stored>(&self, value: &T) -> Result<()> {
+        if self.read_only {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::ReadOnlyFilesystem,
+                "stateless mode",
+            ))
+            .state_context(|| format!("Not writing {}", self.path.join(T::NAME).display()));
+        }
+        std::fs::create_dir_all(&self.path)
+            .state_context(|| format!("Could not create {}", self.path.display()))?;
+
+        let lock = self.lock_file()?;
+        lock.lock()
+            .state_context(|| "Could not lock state directory".to_string())?;
+
+        let json = serde_json::to_string(&Envelope {
+            version: T::VERSION,
+            data: value,
+        })?;
+        write_atomically(&self.path.join(T::NAME), &json)
+    }
+
+    /// The stored value, or `None` if there isn't one or it's from an incompatible version.
+    pub fn read<T: Stored>(&self) -> Option<T> {
+        // readers without write access can't create the lock file, but since writes are atomic
+        // they're still safe to read without it
+        let lock = (!self.read_only).then(|| self.lock_file().ok()).flatten();
+        if let Some(lock) = &lock {
+            lock.lock_shared().ok()?;
+        }
+
+        let json = std::fs::read_to_string(self.path.join(T::NAME)).ok()?;
+        let envelope: OwnedEnvelope<T> = serde_json::from_str(&json).ok()?;
+        (envelope.version == T::VERSION).then_some(envelope.data)
+    }
+}
+
+/// Replace `path` with `contents` in one go, so readers never see a half-written file.
+pub fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+
+    std::fs::write(&temporary, contents)
+        .state_context(|| format!("Could not write {}", Path::new(&temporary).display()))?;
+    std::fs::rename(&temporary, path)
+        .state_context(|| format!("Could not replace {}", path.display()))
+}
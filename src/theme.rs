@@ -0,0 +1,87 @@
+// named colour palettes, so picking colours for the states is one word of config
+// rather than four hex codes.
+
+use crate::error::{Error, Result};
+use crate::State;
+
+/// A colour for each state, `None` leaving it to the bar's default.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub info: Option<&'static str>,
+    pub good: Option<&'static str>,
+    pub warning: Option<&'static str>,
+    pub critical: Option<&'static str>,
+}
+
+impl Theme {
+    pub fn color(&self, state: State) -> Option<&'static str> {
+        match state {
+            State::Info => self.info,
+            State::Good => self.good,
+            State::Warning => self.warning,
+            State::Critical => self.critical,
+        }
+    }
+}
+
+/// Plain traffic lights, what py3status uses out of the box.
+pub const DEFAULT: Theme = Theme {
+    info: None,
+    good: Some("#00FF00"),
+    warning: Some("#FFFF00"),
+    critical: Some("#FF0000"),
+};
+
+pub const GRUVBOX: Theme = Theme {
+    info: Some("#83A598"),
+    good: Some("#B8BB26"),
+    warning: Some("#FABD2F"),
+    critical: Some("#FB4934"),
+};
+
+/// Catppuccin Mocha.
+pub const CATPPUCCIN: Theme = Theme {
+    info: Some("#89B4FA"),
+    good: Some("#A6E3A1"),
+    warning: Some("#F9E2AF"),
+    critical: Some("#F38BA8"),
+};
+
+pub const NORD: Theme = Theme {
+    info: Some("#88C0D0"),
+    good: Some("#A3BE8C"),
+    warning: Some("#EBCB8B"),
+    critical: Some("#BF616A"),
+};
+
+pub const SOLARIZED: Theme = Theme {
+    info: Some("#268BD2"),
+    good: Some("#859900"),
+    warning: Some("#B58900"),
+    critical: Some("#DC322F"),
+};
+
+/// Leave every colour to the bar.
+pub const NONE: Theme = Theme {
+    info: None,
+    good: None,
+    warning: None,
+    critical: None,
+};
+
+/// The theme called `name`. `no_color` (from the `NO_COLOR` convention) overrides it with `NONE`.
+pub fn select(name: &str, no_color: bool) -> Result<&'static Theme> {
+    if no_color {
+        return Ok(&NONE);
+    }
+
+    Ok(match name {
+        "default" => &DEFAULT,
+        "gruvbox" => &GRUVBOX,
+        "catppuccin" => &CATPPUCCIN,
+        "nord" => &NORD,
+        "solarized" => &SOLARIZED,
+        "none" => &NONE,
+        other => return Err(Error::Config(format!("Unknown theme {other}"))),
+    })
+}
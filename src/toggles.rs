@@ -0,0 +1,52 @@
+// switching a check off for a while without a rebuild, e.g. the failed units check while a flaky
+// unit is being dealt with. checks go by their finding label, lowercase with dashes for spaces as
+// in --simulate, and are kept off in the state dir until switched back on or their time is up.
+
+use crate::state_dir::{StateDir, Stored};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Disabled {
+    /// The checks switched off, each with the unix timestamp it comes back on by itself, if any.
+    pub checks: Vec<(String, Option<i64>)>,
+}
+
+impl Stored for Disabled {
+    const NAME: &'static str = "disabled.json";
+    const VERSION: u32 = 1;
+}
+
+impl Disabled {
+    /// The checks switched off in `state_dir`, none if there isn't one. Only looked up, so read
+    /// only: it neither leaves a lock file behind nor waits on a `disable` holding the lock.
+    pub fn load(state_dir: Option<StateDir>) -> Self {
+        state_dir
+            .and_then(|dir| dir.read_only().read::<Disabled>())
+            .unwrap_or_default()
+    }
+
+    /// Whether `check` is off at `now`.
+    pub fn is_off(&self, check: &str, now: i64) -> bool {
+        self.checks
+            .iter()
+            .any(|(name, until)| name == check && until.is_none_or(|until| now < until))
+    }
+
+    /// Switch `check` off, until `until` if that's set, replacing however it was off before.
+    pub fn disable(&mut self, check: &str, until: Option<i64>) {
+        self.enable(check);
+        self.checks.push((check.to_string(), until));
+    }
+
+    pub fn enable(&mut self, check: &str) {
+        self.checks.retain(|(name, _)| name != check);
+    }
+
+    /// The checks still off at `now`.
+    pub fn off(&self, now: i64) -> Vec<&str> {
+        self.checks
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| self.is_off(name, now))
+            .collect()
+    }
+}
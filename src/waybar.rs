@@ -0,0 +1,48 @@
+// waybar's custom module takes one JSON object per line. besides the text it wants a css class to
+// style by and optionally a percentage, which we fill with how far the lock is towards out of date.
+
+use crate::{BarCommand, Finding, State};
+
+#[derive(serde::Serialize, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub text: String,
+    pub tooltip: String,
+    pub class: &'static str,
+    pub percentage: u8,
+}
+
+impl Block {
+    /// `updated` is what the last update changed, if it's recent enough to mention.
+    pub fn new(
+        command: &BarCommand,
+        findings: &[Finding],
+        updated: Option<&str>,
+        percentage: u8,
+    ) -> Block {
+        // the bar text may be summarised or cut off, the tooltip has room for everything
+        let mut tooltip = updated
+            .map(|updated| format!("{updated}\n"))
+            .unwrap_or_default();
+        tooltip.extend(findings.iter().map(Finding::render_details));
+
+        Block {
+            text: command.text.clone(),
+            tooltip: tooltip.trim_end().to_string(),
+            class: match command.state {
+                State::Info => "info",
+                State::Good => "good",
+                State::Warning => "warning",
+                State::Critical => "critical",
+            },
+            percentage,
+        }
+    }
+}
+
+/// How far `duration_days` is towards `out_of_date`, capped at 100.
+pub fn percentage(duration_days: i64, out_of_date: i64) -> u8 {
+    if out_of_date <= 0 {
+        return 100;
+    }
+    (duration_days.max(0) * 100 / out_of_date).min(100) as u8
+}
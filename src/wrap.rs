@@ -0,0 +1,145 @@
+// sits between i3status and i3bar: passes i3status' JSON stream through with our block spliced in,
+// and forwards i3bar's click events back to i3status unless they're for us.
+
+use crate::error::{Error, IoContext, Result};
+use crate::i3bar::{Block, ClickEvent};
+use std::io::{BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// Insert `block` at `position` (clamped to the end) into a status line of an i3bar protocol
+/// stream. Returns `None` for the header, the opening bracket and anything else that isn't a
+/// status line, which should be passed through untouched.
+pub fn inject(line: &str, block: &serde_json::Value, position: usize) -> Option<String> {
+    let trimmed = line.trim();
+    let (prefix, array) = match trimmed.strip_prefix(',') {
+        Some(rest) => (",", rest),
+        None => ("", trimmed),
+    };
+
+    let mut blocks = serde_json::from_str::<Vec<serde_json::Value>>(array).ok()?;
+    blocks.insert(position.min(blocks.len()), block.clone());
+
+    Some(format!("{prefix}{}", serde_json::Value::Array(blocks)))
+}
+
+enum Event {
+    Status(String),
+    Click,
+    Closed,
+}
+
+/// How often to ask whether the session went idle or came back.
+const IDLE_POLL: Duration = Duration::from_secs(10);
+
+/// Run `command` (or read stdin, if the command is `-`) and forward its status lines with our
+/// block spliced in, rebuilding the block at most once per `interval` or when it's clicked.
+/// While `idle` says so the block is left as it is, and it's rebuilt as soon as that ends.
+/// With `no_exec` only reading stdin is allowed.
+pub fn run(
+    command: &[String],
+    no_exec: bool,
+    position: usize,
+    interval: Duration,
+    extra_fields: &str,
+    mut block: impl FnMut() -> Block,
+    mut idle: impl FnMut() -> bool,
+) -> Result<()> {
+    let (sender, events) = std::sync::mpsc::channel();
+
+    let mut current = block();
+    let mut built = Instant::now();
+
+    if command.first().map(String::as_str) == Some("-") {
+        spawn_reader(std::io::BufReader::new(std::io::stdin()), sender);
+    } else {
+        if no_exec {
+            return Err(Error::Config(
+                "--wrap can only read stdin (--wrap -) without exec".to_string(),
+            ));
+        }
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| Error::Config("--wrap needs a command".to_string()))?;
+        let mut child = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .tool_context(|| format!("Could not run {program}"))?;
+
+        // both were asked for as pipes above, so they're always there
+        let stdout = child.stdout.take().expect("piped stdout");
+        spawn_reader(std::io::BufReader::new(stdout), sender.clone());
+
+        let mut child_stdin = child.stdin.take().expect("piped stdin");
+        // name and instance never change, so any copy of the block will do for routing clicks
+        let ours = current.clone();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines().map_while(Result::ok) {
+                let ours = ClickEvent::parse(&line).is_some_and(|click| click.is_for(&ours));
+                if ours {
+                    let _ = sender.send(Event::Click);
+                } else if writeln!(child_stdin, "{line}").is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    // the blocks from the latest status line, so a click can be answered without waiting on the
+    // wrapped command's next line
+    let mut last_status: Option<String> = None;
+    let mut stale = false;
+    let mut is_idle = false;
+    let mut polled = Instant::now();
+    let stdout = std::io::stdout();
+
+    loop {
+        let line = match events.recv().unwrap_or(Event::Closed) {
+            Event::Closed => return Ok(()),
+            Event::Status(line) => line,
+            Event::Click => {
+                stale = true;
+                match &last_status {
+                    Some(blocks) => format!(",{blocks}"),
+                    None => continue,
+                }
+            }
+        };
+
+        if polled.elapsed() >= IDLE_POLL {
+            let was_idle = is_idle;
+            is_idle = idle();
+            polled = Instant::now();
+            stale |= was_idle && !is_idle;
+        }
+
+        if !is_idle && (stale || built.elapsed() >= interval) {
+            current = block();
+            built = Instant::now();
+            stale = false;
+        }
+
+        current.tick();
+        let json = crate::merge_fields(serde_json::to_value(&current)?, extra_fields)?;
+
+        match inject(&line, &json, position) {
+            Some(injected) => {
+                last_status = Some(line.trim().trim_start_matches(',').to_string());
+                writeln!(stdout.lock(), "{injected}")?;
+            }
+            None => writeln!(stdout.lock(), "{line}")?,
+        }
+    }
+}
+
+fn spawn_reader(reader: impl BufRead + Send + 'static, sender: std::sync::mpsc::Sender<Event>) {
+    std::thread::spawn(move || {
+        for line in reader.lines().map_while(Result::ok) {
+            if sender.send(Event::Status(line)).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(Event::Closed);
+    });
+}
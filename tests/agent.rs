@@ -0,0 +1,121 @@
+use i3status_nix_update_widget::agent::{
+    answer, negotiate, request, serve, status, MIN_PROTOCOL, PROTOCOL,
+};
+
+#[test]
+fn agent_answers_each_request() {
+    let input = format!(
+        "{}\n\n{{\"protocol\":1,\"query\":\"reboot\"}}\nnonsense\n",
+        request()
+    );
+    let mut output = Vec::new();
+    let mut asked = 0;
+    serve(input.as_bytes(), &mut output, || {
+        asked += 1;
+        serde_json::json!({"state": "Good", "cause": "age", "text": "Age: 3"})
+    })
+    .unwrap();
+
+    let replies = String::from_utf8(output).unwrap();
+    let replies = replies.lines().collect::<Vec<_>>();
+    assert_eq!(asked, 1);
+    assert_eq!(replies.len(), 3);
+    assert_eq!(
+        status(replies[0]).unwrap(),
+        r#"{"cause":"age","state":"Good","text":"Age: 3"}"#
+    );
+    assert!(status(replies[1])
+        .unwrap_err()
+        .ends_with(r#"unknown query "reboot""#));
+    assert!(status(replies[2]).unwrap_err().contains("bad request"));
+}
+
+#[test]
+fn replies_carry_the_protocol() {
+    let reply = answer(&request(), || serde_json::json!({}));
+    assert_eq!(reply.protocol, PROTOCOL);
+    assert_eq!(reply.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn anything_else_is_not_a_reply() {
+    assert!(status("Age: 3\n")
+        .unwrap_err()
+        .starts_with("not an agent reply"));
+    assert!(status("").is_err());
+}
+
+#[test]
+fn agent_answers_in_the_highest_shared_protocol() {
+    let ask = |protocol: u32| {
+        let request = format!(r#"{{"protocol":{protocol},"query":"status"}}"#);
+        answer(&request, || serde_json::json!({}))
+    };
+
+    let old = ask(MIN_PROTOCOL);
+    assert_eq!(old.protocol, MIN_PROTOCOL);
+    assert!(old.status.is_some());
+
+    let newer = ask(PROTOCOL + 5);
+    assert_eq!(newer.protocol, PROTOCOL);
+    assert_eq!(newer.supported, Some((MIN_PROTOCOL, PROTOCOL)));
+    assert!(newer.status.is_some());
+
+    let ancient = ask(0);
+    assert!(ancient.status.is_none());
+    assert!(ancient.error.unwrap().contains("too old"));
+    assert_eq!(negotiate(0), None);
+    assert_eq!(negotiate(PROTOCOL), Some(PROTOCOL));
+}
+
+#[test]
+fn other_versions_are_read_as_far_as_they_go() {
+    // a newer agent with fields this one doesn't know
+    let newer = r#"{"protocol":1,"version":"9.0.0","hints":[1],"status":{"state":"Info"}}"#;
+    assert_eq!(status(newer).unwrap(), r#"{"state":"Info"}"#);
+
+    // a build from before the agent prints its bar block
+    let block = r#"{"icon":"update","state":"Warning","text":"Age: 12"}"#;
+    let old: serde_json::Value = serde_json::from_str(&status(block).unwrap()).unwrap();
+    assert_eq!(
+        old,
+        serde_json::json!({"state": "Warning", "cause": "no agent", "text": "Age: 12"})
+    );
+}
+
+#[test]
+fn replies_in_a_protocol_not_spoken_are_refused() {
+    for protocol in [MIN_PROTOCOL - 1, PROTOCOL + 1] {
+        let reply =
+            format!(r#"{{"protocol":{protocol},"version":"9.0.0","status":{{"state":"Good"}}}}"#);
+        assert!(status(&reply)
+            .unwrap_err()
+            .contains(&format!("answered in protocol {protocol}")));
+    }
+}
+
+#[test]
+fn old_blocks_are_read_in_every_output_format() {
+    let read = |block: &str| {
+        let status: serde_json::Value = serde_json::from_str(&status(block).unwrap()).unwrap();
+        (status["state"].clone(), status["text"].clone())
+    };
+    let warning = (serde_json::json!("Warning"), serde_json::json!("Age: 12"));
+    let critical = (serde_json::json!("Critical"), serde_json::json!("Age: 40"));
+    let info = (serde_json::json!("Info"), serde_json::json!("Age: 3"));
+
+    assert_eq!(
+        read(r#"{"icon":"","state":"warning","text":"Age: 12"}"#),
+        warning
+    );
+    assert_eq!(read(r#"{"icon":"","state":2,"text":"Age: 12"}"#), warning);
+    assert_eq!(
+        read(r#"{"text":"Age: 12","tooltip":"","class":"warning","percentage":40}"#),
+        warning
+    );
+    assert_eq!(read(r#"{"full_text":"Age: 40","urgent":true}"#), critical);
+    assert_eq!(read(r#"[{"full_text":"Age: 40","urgent":true}]"#), critical);
+    // nothing to tell the state by
+    assert_eq!(read(r#"{"full_text":"Age: 3","urgent":false}"#), info);
+    assert_eq!(read(r#"{"icon":"","state":"meh","text":"Age: 3"}"#), info);
+}
@@ -0,0 +1,43 @@
+use i3status_nix_update_widget::aggregate::{load, rank, table};
+use i3status_nix_update_widget::State;
+
+#[test]
+fn hosts_are_ranked_worst_first() {
+    let dir = tempfile::tempdir().unwrap();
+    for (host, json) in [
+        (
+            "web1",
+            r#"{"state":"Info","cause":"age","reasons":[],"text":"Age: 5","findings":[]}"#,
+        ),
+        (
+            "db1",
+            r#"{"state":"Warning","cause":"Reboot","reasons":["KERNEL_MISMATCH"],"text":"Age: 2 Reboot: kernel"}"#,
+        ),
+        ("broken", "{"),
+    ] {
+        std::fs::write(dir.path().join(format!("{host}.json")), json).unwrap();
+    }
+
+    let hosts = rank(
+        ["web1", "db1", "broken", "missing"]
+            .iter()
+            .map(|host| load(&dir.path().join(format!("{host}.json"))))
+            .collect(),
+    );
+
+    assert_eq!(
+        hosts
+            .iter()
+            .map(|host| (host.host.as_str(), host.state))
+            .collect::<Vec<_>>(),
+        [
+            ("broken", State::Critical),
+            ("missing", State::Critical),
+            ("db1", State::Warning),
+            ("web1", State::Info),
+        ]
+    );
+    assert_eq!(hosts[2].reasons, ["KERNEL_MISMATCH"]);
+    assert!(table(&hosts[2..])
+        .starts_with("db1   Warning   Age: 2 Reboot: kernel\nweb1  Info      Age: 5\n"));
+}
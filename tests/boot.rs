@@ -0,0 +1,257 @@
+use i3status_nix_update_widget::boot::{
+    grub_default_is_latest, parse_extlinux_default, stale_boot_files, stale_extlinux_files,
+    systemd_boot_default_is_latest,
+};
+use std::path::Path;
+
+const EXTLINUX_CONF: &str = "\
+# Generated file, all changes will be lost on nixos-rebuild!
+
+# Change this to e.g. nixos-42 to temporarily boot to an older configuration.
+DEFAULT nixos-default
+
+MENU TITLE ------------------------------------------------------------
+TIMEOUT 50
+
+LABEL nixos-default
+  MENU LABEL NixOS - Default
+  LINUX ../nixos/new-linux-Image
+  INITRD ../nixos/new-initrd
+  APPEND init=@system@/init loglevel=4
+
+LABEL nixos-41
+  MENU LABEL NixOS - Configuration 41
+  LINUX ../nixos/old-linux-Image
+  INITRD ../nixos/old-initrd
+  APPEND init=/nix/store/old-nixos-system/init loglevel=4
+";
+
+#[test]
+fn parses_default_entry() {
+    let conf = EXTLINUX_CONF.replace("@system@", "/nix/store/new-nixos-system");
+    let entry = parse_extlinux_default(&conf, Path::new("/boot/extlinux"));
+    assert_eq!(
+        entry.kernel.as_deref(),
+        Some(Path::new("/boot/extlinux/../nixos/new-linux-Image"))
+    );
+    assert_eq!(
+        entry.initrd.as_deref(),
+        Some(Path::new("/boot/extlinux/../nixos/new-initrd"))
+    );
+    assert_eq!(
+        entry.system.as_deref(),
+        Some(Path::new("/nix/store/new-nixos-system"))
+    );
+}
+
+#[test]
+fn missing_default_has_no_files() {
+    let entry = parse_extlinux_default("LABEL foo\n  LINUX bar\n", Path::new("/boot"));
+    assert_eq!(entry.kernel, None);
+    assert_eq!(entry.initrd, None);
+    assert_eq!(entry.system, None);
+}
+
+fn fixture(kernel_on_boot: Option<&str>, initrd_on_boot: &str) -> tempfile::TempDir {
+    let root = tempfile::tempdir().unwrap();
+    let extlinux = root.path().join("boot/extlinux");
+    let nixos = root.path().join("boot/nixos");
+    let system = root.path().join("system");
+    std::fs::create_dir_all(&extlinux).unwrap();
+    std::fs::create_dir_all(&nixos).unwrap();
+    std::fs::create_dir_all(&system).unwrap();
+
+    std::fs::write(
+        extlinux.join("extlinux.conf"),
+        EXTLINUX_CONF.replace("@system@", &system.to_string_lossy()),
+    )
+    .unwrap();
+    if let Some(kernel) = kernel_on_boot {
+        std::fs::write(nixos.join("new-linux-Image"), kernel).unwrap();
+    }
+    std::fs::write(nixos.join("new-initrd"), initrd_on_boot).unwrap();
+    std::fs::write(system.join("kernel"), "kernel").unwrap();
+    std::fs::write(system.join("initrd"), "initrd").unwrap();
+
+    root
+}
+
+fn stale(root: &tempfile::TempDir) -> Vec<String> {
+    stale_extlinux_files(
+        &root.path().join("boot/extlinux/extlinux.conf"),
+        &root.path().join("system"),
+    )
+    .unwrap()
+}
+
+#[test]
+fn synced_boot_files_are_not_stale() {
+    assert!(stale(&fixture(Some("kernel"), "initrd")).is_empty());
+}
+
+#[test]
+fn changed_boot_files_are_stale() {
+    assert_eq!(stale(&fixture(Some("kernel"), "old initrd")), ["initrd"]);
+    assert_eq!(
+        stale(&fixture(Some("old kernel"), "old initrd")),
+        ["kernel", "initrd"]
+    );
+}
+
+#[test]
+fn missing_boot_files_are_stale() {
+    assert_eq!(stale(&fixture(None, "initrd")), ["kernel"]);
+}
+
+#[test]
+fn extlinux_default_for_another_system_is_stale() {
+    let root = fixture(Some("kernel"), "initrd");
+    let newer = root.path().join("newer-system");
+    std::fs::create_dir_all(&newer).unwrap();
+    std::fs::write(newer.join("kernel"), "kernel").unwrap();
+    std::fs::write(newer.join("initrd"), "initrd").unwrap();
+
+    let stale = stale_extlinux_files(&root.path().join("boot/extlinux/extlinux.conf"), &newer);
+    assert_eq!(stale.unwrap(), ["default entry"]);
+}
+
+fn systemd_boot(default: Option<&str>, entries: &[(&str, &Path)]) -> tempfile::TempDir {
+    let esp = tempfile::tempdir().unwrap();
+    let loader = esp.path().join("loader");
+    std::fs::create_dir_all(loader.join("entries")).unwrap();
+
+    let mut conf = "timeout 5\n".to_string();
+    if let Some(default) = default {
+        conf.push_str(&format!("default {default}\n"));
+    }
+    std::fs::write(loader.join("loader.conf"), conf).unwrap();
+
+    for (name, system) in entries {
+        std::fs::write(
+            loader.join("entries").join(name),
+            format!(
+                "title NixOS\nlinux /efi/nixos/kernel.efi\noptions init={}/init loglevel=4\n",
+                system.display()
+            ),
+        )
+        .unwrap();
+    }
+
+    esp
+}
+
+#[test]
+fn systemd_boot_default_matches_latest_generation() {
+    let store = tempfile::tempdir().unwrap();
+    let old = store.path().join("system-41");
+    let new = store.path().join("system-42");
+    std::fs::create_dir_all(&old).unwrap();
+    std::fs::create_dir_all(&new).unwrap();
+    let profile = store.path().join("system");
+    std::os::unix::fs::symlink(&new, &profile).unwrap();
+
+    let entries = [
+        ("nixos-generation-41.conf", old.as_path()),
+        ("nixos-generation-42.conf", new.as_path()),
+    ];
+    let is_latest = |default| {
+        let esp = systemd_boot(default, &entries);
+        systemd_boot_default_is_latest(&esp.path().join("loader/loader.conf"), &profile).unwrap()
+    };
+
+    assert_eq!(is_latest(Some("nixos-generation-42.conf")), Some(true));
+    assert_eq!(is_latest(Some("nixos-generation-42")), Some(true));
+    assert_eq!(is_latest(Some("nixos-generation-41.conf")), Some(false));
+    assert_eq!(is_latest(Some("nixos-generation-43.conf")), Some(false));
+    assert_eq!(is_latest(None), Some(false));
+    // left to the loader to pick, so there's nothing to compare
+    assert_eq!(is_latest(Some("nixos-*")), None);
+    assert_eq!(is_latest(Some("@saved")), None);
+}
+
+/// Two generations, the profile pointing at the newer one.
+fn generations() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+    let store = tempfile::tempdir().unwrap();
+    let old = store.path().join("system-41");
+    let new = store.path().join("system-42");
+    std::fs::create_dir_all(&old).unwrap();
+    std::fs::create_dir_all(&new).unwrap();
+    std::os::unix::fs::symlink(&new, store.path().join("system")).unwrap();
+    (store, old, new)
+}
+
+/// A grub.cfg laid out like NixOS' GRUB builder writes it, with older generations in a submenu.
+fn grub(default: &str, first: &Path, second: &Path, grubenv: Option<&str>) -> tempfile::TempDir {
+    let boot = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(boot.path().join("grub")).unwrap();
+    let entry = |title: &str, system: &Path| {
+        format!(
+            "menuentry \"{title}\" --class nixos --unrestricted {{\n  search --set=drive1 --fs-uuid 1234\n  linux ($drive1)//kernels/bzImage init={}/init loglevel=4\n  initrd ($drive1)//kernels/initrd\n}}\n",
+            system.display()
+        )
+    };
+    let conf = format!(
+        "if [ -s $prefix/grubenv ]; then\n  load_env\nfi\nif [ \"${{next_entry}}\" ]; then\n  set default=\"${{next_entry}}\"\n  set next_entry=\n  save_env next_entry\nelse\n  set default={default}\nfi\n{}submenu \"NixOS - All configurations\" --class submenu {{\n{}}}\n",
+        entry("NixOS - Default", first),
+        entry("NixOS - Configuration 41", second),
+    );
+    std::fs::write(boot.path().join("grub/grub.cfg"), conf).unwrap();
+    if let Some(grubenv) = grubenv {
+        std::fs::write(boot.path().join("grub/grubenv"), grubenv).unwrap();
+    }
+    boot
+}
+
+#[test]
+fn grub_default_matches_latest_generation() {
+    let (store, old, new) = generations();
+    let profile = store.path().join("system");
+    let is_latest = |default, first: &Path, grubenv| {
+        let boot = grub(default, first, &old, grubenv);
+        grub_default_is_latest(&boot.path().join("grub/grub.cfg"), &profile).unwrap()
+    };
+
+    assert_eq!(is_latest("0", &new, None), Some(true));
+    assert_eq!(is_latest("0", &old, None), Some(false));
+    // the submenu of older generations
+    assert_eq!(is_latest("1", &new, None), Some(false));
+    assert_eq!(is_latest("\"NixOS - Default\"", &new, None), Some(true));
+    let saved = "\"${saved_entry}\"";
+    assert_eq!(
+        is_latest(
+            saved,
+            &new,
+            Some("# GRUB Environment Block\nsaved_entry=0\n")
+        ),
+        Some(true)
+    );
+    assert_eq!(is_latest(saved, &old, None), Some(false));
+    assert_eq!(
+        is_latest(saved, &new, Some("saved_entry=NixOS - Default\n")),
+        Some(true)
+    );
+    assert_eq!(is_latest("7", &new, None), None);
+}
+
+#[test]
+fn the_bootloader_present_is_checked() {
+    let (store, old, new) = generations();
+    let profile = store.path().join("system");
+
+    let boot = grub("0", &old, &new, None);
+    assert_eq!(
+        stale_boot_files(boot.path(), &profile).unwrap(),
+        ["default entry"]
+    );
+
+    let esp = systemd_boot(Some("nixos-*"), &[("nixos-generation-41.conf", &old)]);
+    assert!(stale_boot_files(esp.path(), &profile).unwrap().is_empty());
+    let esp = systemd_boot(Some("@saved"), &[]);
+    assert!(stale_boot_files(esp.path(), &profile).unwrap().is_empty());
+
+    // a container, or a bootloader not known here
+    let nothing = tempfile::tempdir().unwrap();
+    assert!(stale_boot_files(nothing.path(), &profile)
+        .unwrap()
+        .is_empty());
+}
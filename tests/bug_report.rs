@@ -0,0 +1,70 @@
+use i3status_nix_update_widget::bug_report::{journal_lines, state_snapshot};
+
+#[test]
+fn snapshot_has_every_kept_file_but_the_lock() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(root.path().join(".lock"), "").unwrap();
+    std::fs::write(root.path().join("published.json"), "{\"version\":1}\n").unwrap();
+    std::fs::write(root.path().join("disabled.json"), "{\"version\":1}").unwrap();
+
+    assert_eq!(
+        state_snapshot(root.path()),
+        format!(
+            "{}:\n  disabled.json: {{\"version\":1}}\n  published.json: {{\"version\":1}}\n",
+            root.path().display()
+        )
+    );
+
+    std::fs::remove_file(root.path().join("published.json")).unwrap();
+    std::fs::remove_file(root.path().join("disabled.json")).unwrap();
+    assert!(state_snapshot(root.path()).ends_with(":\n  nothing kept\n"));
+    assert!(state_snapshot(&root.path().join("gone")).ends_with("gone: not there\n"));
+}
+
+#[test]
+fn log_lines_are_the_widgets_own() {
+    let root = tempfile::tempdir().unwrap();
+    // stands in for journalctl, saying what it was asked
+    let journalctl = root.path().join("journalctl");
+    std::fs::write(&journalctl, "#!/bin/sh\necho \"$@\"\n").unwrap();
+    std::fs::set_permissions(
+        &journalctl,
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+
+    assert_eq!(
+        journal_lines(&journalctl.to_string_lossy(), 50).unwrap(),
+        "--no-pager --output short-iso --lines 50 --identifier i3status-nix-update-widget\n"
+    );
+    assert!(journal_lines("/nonexistent/journalctl", 50).is_err());
+}
+
+#[test]
+fn report_has_every_section() {
+    let state = tempfile::tempdir().unwrap();
+    let kept = state.path().join("i3status-nix-update-widget");
+    std::fs::create_dir_all(&kept).unwrap();
+    std::fs::write(kept.join("disabled.json"), "{\"version\":1}").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_i3status-nix-update-widget"))
+        .args(["report", "--no-exec"])
+        .env("XDG_STATE_HOME", state.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+
+    for section in ["\nsettings:\n", "\nreport: ", "\nstate:\n", "\nlog:\n"] {
+        assert!(report.contains(section), "no {section:?} in {report}");
+    }
+    assert!(report.starts_with(&format!(
+        "i3status-nix-update-widget {}\n",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert!(report.contains(&format!(
+        "{}:\n  disabled.json: {{\"version\":1}}\n",
+        kept.display()
+    )));
+    assert!(report.ends_with("  not read, journalctl can't be run with no exec\n"));
+}
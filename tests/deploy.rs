@@ -0,0 +1,21 @@
+use i3status_nix_update_widget::deploy::{awaiting, deployed_at};
+
+#[test]
+fn last_deploy_is_when_the_marker_was_touched() {
+    let root = tempfile::tempdir().unwrap();
+    let marker = root.path().join("last-deploy");
+    assert_eq!(deployed_at(&marker).unwrap(), None);
+
+    std::fs::write(&marker, "").unwrap();
+    std::fs::File::open(&marker)
+        .unwrap()
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+        .unwrap();
+    assert_eq!(deployed_at(&marker).unwrap(), Some(1_700_000_000));
+}
+
+#[test]
+fn awaiting_names_the_tool() {
+    assert_eq!(awaiting(Some("colmena")), "awaiting colmena deploy");
+    assert_eq!(awaiting(None), "awaiting deploy");
+}
@@ -0,0 +1,42 @@
+use i3status_nix_update_widget::doctor::{read_denied, try_exec, write_denied, Exec};
+use std::path::Path;
+
+#[test]
+fn reads_are_tried_for_real() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(root.path().join("file"), "contents").unwrap();
+
+    assert_eq!(read_denied(root.path()), None);
+    assert_eq!(read_denied(&root.path().join("file")), None);
+    assert!(read_denied(&root.path().join("missing")).is_some());
+}
+
+#[test]
+fn writes_leave_nothing_behind() {
+    let root = tempfile::tempdir().unwrap();
+
+    assert_eq!(write_denied(&root.path().join("not/yet/created")), None);
+    assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn missing_programs_are_not_denials() {
+    assert_eq!(
+        try_exec(Path::new("/bin/sh"), &["-c", "true"]),
+        Exec::Allowed
+    );
+    assert_eq!(
+        try_exec(Path::new("/nonexistent/program"), &[]),
+        Exec::Missing
+    );
+}
+
+#[test]
+fn programs_that_may_not_run_are_denied() {
+    let root = tempfile::tempdir().unwrap();
+    // not executable, as a noexec mount or a missing x bit leaves it
+    let program = root.path().join("program");
+    std::fs::write(&program, "#!/bin/sh\n").unwrap();
+
+    assert!(matches!(try_exec(&program, &[]), Exec::Denied(_)));
+}
@@ -0,0 +1,34 @@
+use i3status_nix_update_widget::etc_drift::drifted;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn replaced_and_missing_files_have_drifted() {
+    let root = tempfile::tempdir().unwrap();
+    let system = root.path().join("system/etc");
+    let live = root.path().join("etc");
+    std::fs::create_dir_all(system.join("ssh")).unwrap();
+    std::fs::create_dir_all(live.join("ssh")).unwrap();
+    for file in ["hosts", "ssh/sshd_config", "resolv.conf", "gone"] {
+        std::fs::write(system.join(file), file).unwrap();
+    }
+
+    symlink(system.join("hosts"), live.join("hosts")).unwrap();
+    std::fs::write(live.join("ssh/sshd_config"), "edited by hand").unwrap();
+    // copied instead of linked, but unchanged
+    std::fs::write(live.join("resolv.conf"), "resolv.conf").unwrap();
+
+    assert_eq!(
+        drifted(
+            &live,
+            &system,
+            &[
+                "hosts",
+                "ssh/sshd_config",
+                "resolv.conf",
+                "gone",
+                "not-shipped"
+            ]
+        ),
+        ["ssh/sshd_config", "gone"]
+    );
+}
@@ -0,0 +1,11 @@
+use i3status_nix_update_widget::failed_units::parse;
+
+#[test]
+fn failed_units_are_listed_by_name() {
+    let output = "\
+nix-gc.service    loaded failed failed Nix Garbage Collector
+● backup.timer loaded failed failed Backup
+";
+    assert_eq!(parse(output), ["nix-gc.service", "backup.timer"]);
+    assert!(parse("").is_empty());
+}
@@ -0,0 +1,37 @@
+use i3status_nix_update_widget::gc_roots::unprotected;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn booted_system_needs_a_generation_and_a_gc_root() {
+    let root = tempfile::tempdir().unwrap();
+    let store = root.path().join("store");
+    for system in ["aaa-nixos-system", "bbb-nixos-system"] {
+        std::fs::create_dir_all(store.join(system)).unwrap();
+    }
+    let profiles = root.path().join("profiles");
+    let gc_roots = root.path().join("gcroots");
+    std::fs::create_dir_all(&profiles).unwrap();
+    std::fs::create_dir_all(&gc_roots).unwrap();
+    let booted = root.path().join("booted-system");
+    symlink(store.join("aaa-nixos-system"), &booted).unwrap();
+    symlink(
+        store.join("bbb-nixos-system"),
+        profiles.join("system-2-link"),
+    )
+    .unwrap();
+    symlink("system-2-link", profiles.join("system")).unwrap();
+    let system = profiles.join("system");
+
+    assert_eq!(
+        unprotected(&booted, &system, &gc_roots).unwrap(),
+        ["generation", "gc root"]
+    );
+
+    symlink(
+        store.join("aaa-nixos-system"),
+        profiles.join("system-1-link"),
+    )
+    .unwrap();
+    symlink(&booted, gc_roots.join("booted-system")).unwrap();
+    assert!(unprotected(&booted, &system, &gc_roots).unwrap().is_empty());
+}
@@ -0,0 +1,88 @@
+use i3status_nix_update_widget::hooks::{due, run, Hook, Renotify, Seen};
+use i3status_nix_update_widget::State;
+
+const HOUR: i64 = 60 * 60;
+
+const SEEN: Seen = Seen {
+    state: State::Good,
+    reboot_needed: false,
+    modified_date: 1697089731,
+    state_fired_at: None,
+    reboot_fired_at: None,
+};
+
+const NEVER: Renotify = Renotify {
+    critical: None,
+    reboot: None,
+};
+
+#[test]
+fn nothing_fires_on_the_first_run() {
+    let mut first = SEEN.clone();
+    assert!(due(None, &mut first, 0, &NEVER).is_empty());
+    assert!(due(Some(&first), &mut SEEN.clone(), HOUR, &NEVER).is_empty());
+}
+
+#[test]
+fn each_change_fires_its_hook() {
+    let mut now = Seen {
+        state: State::Warning,
+        reboot_needed: true,
+        modified_date: SEEN.modified_date + 1,
+        ..SEEN.clone()
+    };
+    assert_eq!(
+        due(Some(&SEEN), &mut now, HOUR, &NEVER),
+        [Hook::StateChange, Hook::RebootNeeded, Hook::UpdateDetected]
+    );
+    assert_eq!(now.state_fired_at, Some(HOUR));
+
+    // only becoming needed counts, not staying needed or going away
+    assert!(due(Some(&now.clone()), &mut now, 2 * HOUR, &NEVER).is_empty());
+    let mut rebooted = Seen {
+        reboot_needed: false,
+        ..now.clone()
+    };
+    assert!(due(Some(&now), &mut rebooted, 3 * HOUR, &NEVER).is_empty());
+}
+
+#[test]
+fn lasting_critical_and_reboots_are_reminded_of() {
+    let every = Renotify {
+        critical: Some(6),
+        reboot: Some(24),
+    };
+    let mut critical = Seen {
+        state: State::Critical,
+        reboot_needed: true,
+        ..SEEN.clone()
+    };
+    assert_eq!(
+        due(Some(&SEEN), &mut critical, 0, &every),
+        [Hook::StateChange, Hook::RebootNeeded]
+    );
+
+    let mut later = critical.clone();
+    assert!(due(Some(&critical), &mut later, 5 * HOUR, &every).is_empty());
+    assert_eq!(later.state_fired_at, Some(0));
+
+    let mut reminded = later.clone();
+    assert_eq!(
+        due(Some(&later), &mut reminded, 6 * HOUR, &every),
+        [Hook::StateChange]
+    );
+    assert_eq!(reminded.state_fired_at, Some(6 * HOUR));
+
+    let mut day = reminded.clone();
+    assert_eq!(
+        due(Some(&reminded), &mut day, 24 * HOUR, &every),
+        [Hook::StateChange, Hook::RebootNeeded]
+    );
+}
+
+#[test]
+fn hooks_get_the_status_and_must_succeed() {
+    run("cat", r#"{"state":"Good"}"#).unwrap();
+    assert!(run("false", "").is_err());
+    assert!(run("/nonexistent/hook", "").is_err());
+}
@@ -0,0 +1,128 @@
+use i3status_nix_update_widget::i3bar::{Block, ClickEvent, Layout};
+use i3status_nix_update_widget::{theme, BarCommand, State};
+
+fn command(state: State) -> BarCommand {
+    BarCommand {
+        icon: "cogs".to_string(),
+        state,
+        text: "Age: 9".to_string(),
+    }
+}
+
+#[test]
+fn bare_block_only_has_text_and_urgency() {
+    let block = Block::new(&command(State::Good), &Layout::default(), &theme::NONE);
+    assert_eq!(
+        serde_json::to_string(&block).unwrap(),
+        r#"{"full_text":"Age: 9","urgent":false}"#
+    );
+}
+
+#[test]
+fn layout_hints_are_emitted() {
+    let layout = Layout {
+        name: None,
+        instance: None,
+        min_width: Some("Age: 00"),
+        align: Some("right"),
+        separator_block_width: Some(15),
+    };
+    let block = Block::new(&command(State::Warning), &layout, &theme::NONE);
+    assert_eq!(
+        serde_json::to_string(&block).unwrap(),
+        r#"{"full_text":"Age: 9","min_width":"Age: 00","align":"right","separator_block_width":15,"urgent":false}"#
+    );
+}
+
+#[test]
+fn critical_is_urgent() {
+    assert!(Block::new(&command(State::Critical), &Layout::default(), &theme::NONE).urgent);
+}
+
+#[test]
+fn name_and_instance_are_emitted() {
+    let layout = Layout {
+        name: Some("nix-update"),
+        instance: Some("desktop"),
+        ..Layout::default()
+    };
+    let block = Block::new(&command(State::Good), &layout, &theme::NONE);
+    assert_eq!(
+        serde_json::to_string(&block).unwrap(),
+        r#"{"full_text":"Age: 9","name":"nix-update","instance":"desktop","urgent":false}"#
+    );
+}
+
+#[test]
+fn parses_click_event_stream_lines() {
+    let first = ClickEvent::parse(r#"[{"name":"nix-update","button":1,"x":10,"y":5}"#).unwrap();
+    assert_eq!(first.name.as_deref(), Some("nix-update"));
+    assert_eq!(first.instance, None);
+    assert_eq!(first.button, 1);
+
+    let later = ClickEvent::parse(r#",{"name":"clock","instance":"utc","button":3}"#).unwrap();
+    assert_eq!(later.name.as_deref(), Some("clock"));
+    assert_eq!(later.instance.as_deref(), Some("utc"));
+
+    assert_eq!(ClickEvent::parse("["), None);
+}
+
+#[test]
+fn clicks_are_routed_by_name_and_instance() {
+    let layout = Layout {
+        name: Some("nix-update"),
+        instance: Some("desktop"),
+        ..Layout::default()
+    };
+    let block = Block::new(&command(State::Good), &layout, &theme::NONE);
+
+    let click = |line| ClickEvent::parse(line).unwrap().is_for(&block);
+    assert!(click(
+        r#"{"name":"nix-update","instance":"desktop","button":1}"#
+    ));
+    assert!(!click(
+        r#"{"name":"nix-update","instance":"laptop","button":1}"#
+    ));
+    assert!(!click(r#"{"name":"nix-update","button":1}"#));
+    assert!(!click(
+        r#"{"name":"clock","instance":"desktop","button":1}"#
+    ));
+}
+
+#[test]
+fn theme_colours_text_and_border() {
+    let block = Block::new(&command(State::Warning), &Layout::default(), &theme::NORD);
+    assert_eq!(
+        serde_json::to_string(&block).unwrap(),
+        r##"{"full_text":"Age: 9","color":"#EBCB8B","border":"#EBCB8B","urgent":false}"##
+    );
+}
+
+#[test]
+fn only_critical_blocks_long_past_out_of_date_blink() {
+    let block = |state| Block::new(&command(state), &Layout::default(), &theme::NONE);
+    for state in [State::Good, State::Info, State::Warning] {
+        assert!(!block(state).blinking(30, Some(7)).blink);
+    }
+    assert!(!block(State::Critical).blinking(3, Some(7)).blink);
+    assert!(!block(State::Critical).blinking(30, None).blink);
+
+    let mut blinking = block(State::Critical).blinking(7, Some(7));
+    assert!(blinking.blink);
+    // never sent to the bar, only the urgent flag it toggles is
+    assert!(serde_json::to_value(&blinking)
+        .unwrap()
+        .get("blink")
+        .is_none());
+    let urgent = (0..4)
+        .map(|_| {
+            blinking.tick();
+            blinking.urgent
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(urgent, [false, true, false, true]);
+
+    let mut steady = block(State::Critical).blinking(3, Some(7));
+    steady.tick();
+    assert!(steady.urgent);
+}
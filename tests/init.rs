@@ -0,0 +1,47 @@
+use i3status_nix_update_widget::init::{detect, parse_svstat, Init, Runit};
+use std::path::Path;
+
+fn service(dir: &Path, name: &str, stat: &str, down: bool) {
+    let service = dir.join(name);
+    std::fs::create_dir_all(service.join("supervise")).unwrap();
+    std::fs::write(service.join("supervise/stat"), stat).unwrap();
+    if down {
+        std::fs::write(service.join("down"), "").unwrap();
+    }
+}
+
+#[test]
+fn runit_services_down_against_their_wishes_failed() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = root.path().join("var/service");
+    service(&dir, "sshd", "run\n", false);
+    service(&dir, "nix-daemon", "down\n", false);
+    service(&dir, "backup", "down\n", true);
+    service(&dir, "cups", "finish, want down\n", false);
+    service(&dir, "dhcpcd", "finish\n", false);
+
+    assert_eq!(Runit { dir }.failed().unwrap(), ["dhcpcd", "nix-daemon"]);
+}
+
+#[test]
+fn init_is_told_from_run() {
+    let root = tempfile::tempdir().unwrap();
+    assert!(detect(root.path()).is_none());
+
+    std::fs::create_dir_all(root.path().join("run/runit")).unwrap();
+    // runit without a service directory isn't much use
+    assert!(detect(root.path()).is_none());
+    std::fs::create_dir_all(root.path().join("etc/service")).unwrap();
+    assert!(!detect(root.path()).unwrap().runs_programs());
+
+    std::fs::create_dir_all(root.path().join("run/systemd/system")).unwrap();
+    assert!(detect(root.path()).unwrap().runs_programs());
+}
+
+#[test]
+fn s6_services_wanted_up_but_down_failed() {
+    assert!(parse_svstat("false true\n"));
+    assert!(!parse_svstat("true true\n"));
+    assert!(!parse_svstat("false false\n"));
+    assert!(!parse_svstat(""));
+}
@@ -0,0 +1,15 @@
+use i3status_nix_update_widget::install::installed_at;
+
+#[test]
+fn install_time_is_when_the_machine_id_was_written() {
+    let root = tempfile::tempdir().unwrap();
+    let machine_id = root.path().join("machine-id");
+    assert_eq!(installed_at(&machine_id).unwrap(), None);
+
+    std::fs::write(&machine_id, "0123456789abcdef\n").unwrap();
+    let written = std::fs::File::open(&machine_id).unwrap();
+    written
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+        .unwrap();
+    assert_eq!(installed_at(&machine_id).unwrap(), Some(1_700_000_000));
+}
@@ -0,0 +1,64 @@
+use i3status_nix_update_widget::journal::{connected, entry, send, span_fields};
+use i3status_nix_update_widget::Span;
+use std::os::unix::fs::MetadataExt;
+use std::time::Duration;
+
+#[test]
+fn entries_use_the_native_protocol() {
+    let entry = entry(&[
+        ("MESSAGE", "reboot check took 3ms".to_string()),
+        ("ERROR", "first\nsecond".to_string()),
+    ]);
+
+    let mut expected = b"MESSAGE=reboot check took 3ms\nERROR\n".to_vec();
+    expected.extend_from_slice(&12u64.to_le_bytes());
+    expected.extend_from_slice(b"first\nsecond\n");
+    assert_eq!(entry, expected);
+}
+
+#[test]
+fn check_runs_become_structured_fields() {
+    let span = Span {
+        check: "failed".to_string(),
+        start: 0,
+        duration: Duration::from_millis(42),
+        error: Some("systemctl failed: no bus".to_string()),
+    };
+    let fields = span_fields(&span);
+
+    assert!(fields.contains(&("CHECK", "failed".to_string())));
+    assert!(fields.contains(&("DURATION_MS", "42".to_string())));
+    assert!(fields.contains(&("RESULT", "error".to_string())));
+    assert!(fields.contains(&("PRIORITY", "4".to_string())));
+
+    let fine = span_fields(&Span {
+        error: None,
+        ..span
+    });
+    assert!(fine.contains(&("RESULT", "ok".to_string())));
+    assert!(fine.contains(&("MESSAGE", "failed check took 42ms".to_string())));
+}
+
+#[test]
+fn journal_stream_must_be_stderr() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let meta = file.as_file().metadata().unwrap();
+    let stream = format!("{}:{}", meta.dev(), meta.ino());
+
+    assert!(connected(Some(&stream), file.path()));
+    assert!(!connected(Some("1:2"), file.path()));
+    assert!(!connected(Some("nonsense"), file.path()));
+    assert!(!connected(None, file.path()));
+}
+
+#[test]
+fn entries_are_sent_as_datagrams() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("socket");
+    let journal = std::os::unix::net::UnixDatagram::bind(&socket).unwrap();
+
+    send(&socket, b"MESSAGE=hi\n").unwrap();
+    let mut buffer = [0; 64];
+    let read = journal.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[..read], b"MESSAGE=hi\n");
+}
@@ -0,0 +1,29 @@
+use i3status_nix_update_widget::kernel_age::{build_date, registration_time};
+
+#[test]
+fn build_date_comes_from_proc_version() {
+    let version = "Linux version 6.1.0-21-amd64 (debian-kernel@lists.debian.org) (gcc-12 (Debian 12.2.0-14) 12.2.0) #1 SMP PREEMPT_DYNAMIC Debian 6.1.90-1 (2024-05-03) Tue May  7 10:15:35 UTC 2024\n";
+    assert_eq!(
+        build_date(version).unwrap().to_rfc3339(),
+        "2024-05-07T10:15:35+00:00"
+    );
+
+    let reproducible = "Linux version 6.6.30 (nixbld@localhost) (gcc (GCC) 13.2.0, GNU ld (GNU Binutils) 2.41) #1-NixOS SMP PREEMPT_DYNAMIC Tue Jan  1 00:00:00 UTC 1980\n";
+    assert_eq!(build_date(reproducible), None);
+    assert_eq!(build_date("garbage"), None);
+}
+
+#[test]
+fn registration_time_in_old_and_new_formats() {
+    assert_eq!(
+        registration_time(
+            r#"[{"path":"/nix/store/aaa-linux-6.6.30","registrationTime":1715000000}]"#
+        ),
+        Some(1715000000)
+    );
+    assert_eq!(
+        registration_time(r#"{"/nix/store/aaa-linux-6.6.30":{"registrationTime":1715000000}}"#),
+        Some(1715000000)
+    );
+    assert_eq!(registration_time("[]"), None);
+}
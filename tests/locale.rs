@@ -0,0 +1,33 @@
+use i3status_nix_update_widget::locale::{language, relative_age, select};
+
+#[test]
+fn language_is_taken_from_the_locale() {
+    assert_eq!(language("de_DE.UTF-8"), Some("de"));
+    assert_eq!(language("fr"), Some("fr"));
+    assert_eq!(language("sr_RS@latin"), Some("sr"));
+    assert_eq!(language("C.UTF-8"), None);
+    assert_eq!(language("POSIX"), None);
+}
+
+#[test]
+fn auto_goes_by_the_locale_variables_in_order() {
+    let vars = |name: &str| match name {
+        "LC_ALL" => Some(String::new()),
+        "LC_MESSAGES" => Some("nl_NL.UTF-8".to_string()),
+        "LANG" => Some("de_DE.UTF-8".to_string()),
+        _ => None,
+    };
+    assert_eq!(select("auto", vars), "nl");
+    assert_eq!(select("auto", |_| Some("C".to_string())), "en");
+    assert_eq!(select("fr", vars), "fr");
+}
+
+#[test]
+fn phrases_agree_with_the_count() {
+    assert_eq!(relative_age(3, "de"), "vor 3 Tagen");
+    assert_eq!(relative_age(1, "de"), "vor 1 Tag");
+    assert_eq!(relative_age(3, "fr"), "il y a 3 jours");
+    assert_eq!(relative_age(0, "es"), "hoy");
+    assert_eq!(relative_age(1, "en"), "1 day ago");
+    assert_eq!(relative_age(5, "xx"), "5 days ago");
+}
@@ -0,0 +1,109 @@
+use i3status_nix_update_widget::lock_diff::{advance, moved, record, this_week, LockHistory};
+use i3status_nix_update_widget::state_dir::StateDir;
+
+const DAY: i64 = 24 * 60 * 60;
+
+#[test]
+fn moved_inputs_are_listed_with_how_far() {
+    let before = vec![
+        ("nixpkgs".to_string(), 100 * DAY),
+        ("home-manager".to_string(), 90 * DAY),
+        ("flake-utils".to_string(), 50 * DAY),
+        ("old".to_string(), 10 * DAY),
+    ];
+    let after = [
+        ("nixpkgs", 109 * DAY),
+        ("home-manager", 104 * DAY),
+        ("flake-utils", 50 * DAY),
+        ("crane", 80 * DAY),
+    ];
+    assert_eq!(
+        moved(&before, &after),
+        [
+            "nixpkgs +9d",
+            "home-manager +14d",
+            "crane new",
+            "old removed"
+        ]
+    );
+}
+
+#[test]
+fn history_only_changes_when_the_inputs_do() {
+    let first = advance(None, &[("nixpkgs", 100 * DAY)], 1000 * DAY);
+    assert_eq!(first.changed_at, None);
+    assert!(first.moved.is_empty());
+
+    let rerun = advance(Some(first), &[("nixpkgs", 100 * DAY)], 1001 * DAY);
+    assert_eq!(rerun.changed_at, None);
+
+    let updated = advance(Some(rerun), &[("nixpkgs", 103 * DAY)], 1002 * DAY);
+    assert_eq!(updated.changed_at, Some(1002 * DAY));
+    assert_eq!(updated.moved, ["nixpkgs +3d"]);
+
+    let later = advance(Some(updated), &[("nixpkgs", 103 * DAY)], 1005 * DAY);
+    assert_eq!(later.changed_at, Some(1002 * DAY));
+}
+
+#[test]
+fn the_digest_only_lists_changes_from_the_last_week() {
+    let now = 1000 * DAY;
+    let first = advance(None, &[("nixpkgs", 100 * DAY)], now - 30 * DAY);
+    let updated = advance(
+        Some(first),
+        &[("nixpkgs", 109 * DAY), ("crane", 80 * DAY)],
+        now - 7 * DAY,
+    );
+    assert_eq!(
+        this_week(Some(&updated), now),
+        "Updated this week: nixpkgs +9d, crane new"
+    );
+    assert_eq!(this_week(Some(&updated), now + 1), "No updates this week");
+
+    let never_changed = advance(None, &[("nixpkgs", 100 * DAY)], now);
+    assert_eq!(this_week(Some(&never_changed), now), "No updates this week");
+    assert_eq!(this_week(None, now), "No updates this week");
+}
+
+#[test]
+fn history_that_cant_be_saved_is_noted_and_still_used() {
+    let root = tempfile::tempdir().unwrap();
+    let configured = StateDir::user_or(root.path().to_str()).unwrap();
+    let mut notes = Vec::new();
+    record(
+        &configured,
+        &[("nixpkgs", 100 * DAY)],
+        1000 * DAY,
+        &mut notes,
+    );
+    assert!(notes.is_empty());
+    assert!(root.path().join("lock_history.json").exists());
+
+    let read_only = configured.read_only();
+    let history = record(
+        &read_only,
+        &[("nixpkgs", 109 * DAY)],
+        1001 * DAY,
+        &mut notes,
+    );
+    assert_eq!(history.changed_at, Some(1001 * DAY));
+    assert_eq!(history.moved, ["nixpkgs +9d"]);
+    assert_eq!(notes.len(), 1);
+    assert!(
+        notes[0].starts_with("lock history not saved: "),
+        "{notes:?}"
+    );
+    // what was saved before is untouched
+    let kept = read_only.read::<LockHistory>();
+    assert_eq!(kept.unwrap().changed_at, None);
+
+    // nothing moved, so there's nothing to save and nothing to note
+    let mut notes = Vec::new();
+    record(
+        &read_only,
+        &[("nixpkgs", 100 * DAY)],
+        1002 * DAY,
+        &mut notes,
+    );
+    assert!(notes.is_empty());
+}
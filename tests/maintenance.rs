@@ -0,0 +1,75 @@
+use chrono::TimeZone;
+use i3status_nix_update_widget::maintenance::{parse_ics, planned, Maintenance, Planned};
+
+const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Update the l\r
+ aptop\r
+DTSTART:20240504T090000Z\r
+DTEND:20240504T120000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART;TZID=Europe/Berlin:20240511T090000\r
+DTEND;TZID=Europe/Berlin:20240511T120000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART;VALUE=DATE:20240518\r
+DTEND;VALUE=DATE:20240519\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+DTSTART:nonsense\r
+DTEND:20240525T120000Z\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+fn utc(day: u32, hour: u32) -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc
+        .with_ymd_and_hms(2024, 5, day, hour, 0, 0)
+        .unwrap()
+}
+
+#[test]
+fn events_become_windows() {
+    let offset = chrono::FixedOffset::east_opt(2 * 60 * 60).unwrap();
+    assert_eq!(
+        parse_ics(ICS, &offset),
+        [
+            Maintenance {
+                start: utc(4, 9),
+                end: utc(4, 12),
+            },
+            Maintenance {
+                start: utc(11, 7),
+                end: utc(11, 10),
+            },
+            Maintenance {
+                start: utc(17, 22),
+                end: utc(18, 22),
+            },
+        ]
+    );
+}
+
+#[test]
+fn windows_hold_off_and_missed_ones_escalate() {
+    let windows = [
+        Maintenance {
+            start: utc(4, 9),
+            end: utc(4, 12),
+        },
+        Maintenance {
+            start: utc(11, 9),
+            end: utc(11, 12),
+        },
+    ];
+    let locked = utc(1, 0);
+
+    assert_eq!(planned(&windows, utc(3, 0), locked), Planned::Nothing);
+    assert_eq!(planned(&windows, utc(4, 10), locked), Planned::During);
+    assert_eq!(planned(&windows, utc(5, 0), locked), Planned::Missed);
+    // updated during the window
+    assert_eq!(planned(&windows, utc(5, 0), utc(4, 10)), Planned::Nothing);
+    assert_eq!(planned(&[], utc(5, 0), locked), Planned::Nothing);
+}
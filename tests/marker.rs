@@ -0,0 +1,47 @@
+use i3status_nix_update_widget::marker::{self, Marker};
+use i3status_nix_update_widget::state_dir::StateDir;
+use std::os::unix::fs::symlink;
+
+#[test]
+fn reads_generation_from_profile_link() {
+    let profiles = tempfile::tempdir().unwrap();
+    let profile = profiles.path().join("system");
+    symlink("system-42-link", &profile).unwrap();
+
+    assert_eq!(marker::generation(&profile), Some(42));
+    assert_eq!(marker::generation(&profiles.path().join("missing")), None);
+}
+
+#[test]
+fn round_trips_and_checks_currency() {
+    let root = tempfile::tempdir().unwrap();
+    let system = root.path().join("store/abc-nixos-system");
+    std::fs::create_dir_all(&system).unwrap();
+    let current = root.path().join("current-system");
+    symlink(&system, &current).unwrap();
+
+    let dir = StateDir::at(root.path().join("state"));
+    assert_eq!(dir.read::<Marker>(), None);
+
+    let written = Marker {
+        generation: Some(42),
+        system: std::fs::canonicalize(&system).unwrap(),
+        timestamp: 1_700_000_000,
+        changed: vec!["kernel".to_string()],
+    };
+    dir.write(&written).unwrap();
+
+    let read = dir.read::<Marker>().unwrap();
+    assert_eq!(read, written);
+
+    assert!(read.is_current(&current, 1_699_999_000));
+    // written before the last boot
+    assert!(!read.is_current(&current, 1_700_000_001));
+
+    // switched to another system since
+    let other = root.path().join("store/def-nixos-system");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::remove_file(&current).unwrap();
+    symlink(&other, &current).unwrap();
+    assert!(!read.is_current(&current, 1_699_999_000));
+}
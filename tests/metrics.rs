@@ -0,0 +1,51 @@
+use i3status_nix_update_widget::metrics::render;
+use i3status_nix_update_widget::{Finding, State};
+
+#[test]
+fn renders_textfile_metrics() {
+    let findings = [Finding {
+        label: "Reboot".to_string(),
+        items: vec!["kernel".to_string(), "initrd".to_string()],
+    }];
+
+    assert_eq!(
+        render(
+            12,
+            &[("home-manager", 30), ("nixpkgs", 12)],
+            State::Warning,
+            &findings
+        ),
+        "\
+# HELP nix_update_flake_age_days Days since the most recently updated flake input was last modified.
+# TYPE nix_update_flake_age_days gauge
+nix_update_flake_age_days 12
+# HELP nix_update_flake_input_age_days Days since each flake input was last modified.
+# TYPE nix_update_flake_input_age_days gauge
+nix_update_flake_input_age_days{input=\"home-manager\"} 30
+nix_update_flake_input_age_days{input=\"nixpkgs\"} 12
+# HELP nix_update_state 1 for the state the widget is currently in, 0 for the others.
+# TYPE nix_update_state gauge
+nix_update_state{state=\"Info\"} 0
+nix_update_state{state=\"Good\"} 0
+nix_update_state{state=\"Warning\"} 1
+nix_update_state{state=\"Critical\"} 0
+# HELP nix_update_findings Number of items each check found needing attention.
+# TYPE nix_update_findings gauge
+nix_update_findings{check=\"Reboot\"} 2
+# HELP nix_update_pending_component 1 for each item a check found needing attention.
+# TYPE nix_update_pending_component gauge
+nix_update_pending_component{check=\"Reboot\",name=\"kernel\"} 1
+nix_update_pending_component{check=\"Reboot\",name=\"initrd\"} 1
+"
+    );
+}
+
+#[test]
+fn escapes_label_values() {
+    let findings = [Finding {
+        label: "Restart".to_string(),
+        items: vec![r#"odd "name"\"#.to_string()],
+    }];
+    assert!(render(0, &[], State::Good, &findings)
+        .contains(r#"nix_update_pending_component{check="Restart",name="odd \"name\"\\"} 1"#));
+}
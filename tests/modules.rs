@@ -0,0 +1,252 @@
+use i3status_nix_update_widget::modules::{
+    changed_modules, collapse, elf_modinfo, identity, kernel_version, mismatched_modules, Changed,
+};
+use i3status_nix_update_widget::Finding;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+/// A system built like NixOS' aggregateModules does: the kernel and each module package merged
+/// by buildEnv into one tree of symlinks into the store, each file under the version it was built
+/// for, and depmod run on the kernel's own version.
+fn system(root: &Path, kernel: &str, modules: &[(&str, &str)]) -> std::path::PathBuf {
+    let system = root.join("system");
+    let package = root.join(format!("store/linux-{kernel}"));
+    std::fs::create_dir_all(package.join(format!("lib/modules/{kernel}/kernel"))).unwrap();
+    std::fs::write(package.join("bzImage"), "").unwrap();
+    std::fs::create_dir_all(&system).unwrap();
+    symlink(package.join("bzImage"), system.join("kernel")).unwrap();
+
+    let tree = system.join("kernel-modules/lib/modules");
+    std::fs::create_dir_all(tree.join(kernel)).unwrap();
+    std::fs::write(tree.join(kernel).join("modules.dep"), "").unwrap();
+
+    for (file, built_for) in modules {
+        let store = root.join(format!(
+            "store/{file}-kernel-{built_for}/lib/modules/{built_for}/extra"
+        ));
+        std::fs::create_dir_all(&store).unwrap();
+        std::fs::write(store.join(file), "").unwrap();
+        let extra = tree.join(built_for).join("extra");
+        std::fs::create_dir_all(&extra).unwrap();
+        symlink(store.join(file), extra.join(file)).unwrap();
+    }
+
+    system
+}
+
+#[test]
+fn module_built_for_the_kernel_is_paired() {
+    let root = tempfile::tempdir().unwrap();
+    let system = system(root.path(), "6.6.20", &[("zfs.ko.xz", "6.6.20")]);
+
+    assert!(mismatched_modules(&system, &["zfs"]).unwrap().is_empty());
+}
+
+#[test]
+fn module_built_for_another_kernel_is_unpaired() {
+    let root = tempfile::tempdir().unwrap();
+    let system = system(root.path(), "6.7.1", &[("zfs.ko.xz", "6.6.20")]);
+
+    assert_eq!(mismatched_modules(&system, &["zfs"]).unwrap(), ["zfs"]);
+}
+
+#[test]
+fn kernel_version_is_the_kernels_own() {
+    let root = tempfile::tempdir().unwrap();
+    let system = system(root.path(), "6.7.1", &[("zfs.ko.xz", "6.6.20")]);
+    assert_eq!(kernel_version(&system).as_deref(), Some("6.7.1"));
+
+    // without a kernel package to go by, the directory depmod ran on
+    std::fs::remove_file(system.join("kernel")).unwrap();
+    assert_eq!(kernel_version(&system).as_deref(), Some("6.7.1"));
+    std::fs::remove_file(system.join("kernel-modules/lib/modules/6.7.1/modules.dep")).unwrap();
+    assert_eq!(kernel_version(&system), None);
+    assert!(mismatched_modules(&system, &["zfs"]).is_err());
+}
+
+#[test]
+fn missing_module_is_unpaired() {
+    let root = tempfile::tempdir().unwrap();
+    let system = system(root.path(), "6.6.20", &[("spl.ko", "6.6.20")]);
+
+    assert_eq!(
+        mismatched_modules(&system, &["zfs", "spl"]).unwrap(),
+        ["zfs"]
+    );
+}
+
+#[test]
+fn identity_skips_placeholder_versions() {
+    assert_eq!(
+        identity("filename: zfs.ko\nversion: 2.2.4-1\nsrcversion: ABC\n").as_deref(),
+        Some("version 2.2.4-1")
+    );
+    assert_eq!(
+        identity("version: #VERSION#\nsrcversion: 8F2E1\nvermagic: 6.6.30 SMP\n").as_deref(),
+        Some("srcversion 8F2E1")
+    );
+    assert_eq!(
+        identity("vermagic: 6.6.30 SMP mod_unload\n").as_deref(),
+        Some("vermagic 6.6.30 SMP mod_unload")
+    );
+    assert_eq!(identity("filename: xone.ko\n"), None);
+}
+
+#[test]
+fn modules_are_compared_by_modinfo() {
+    let root = tempfile::tempdir().unwrap();
+    let mut systems = Vec::new();
+    for (name, xone, zfs, blob) in [
+        (
+            "booted",
+            "version: #VERSION#\nsrcversion: AAA\n",
+            "version: 2.2.4\n",
+            "old",
+        ),
+        (
+            "current",
+            "version: #VERSION#\nsrcversion: BBB\n",
+            "version: 2.2.4\n",
+            "new",
+        ),
+    ] {
+        let dir = root
+            .path()
+            .join(name)
+            .join("kernel-modules/lib/modules/6.6.30/extra");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.with_file_name("modules.dep"), "").unwrap();
+        std::fs::write(dir.join("xone.ko"), xone).unwrap();
+        std::fs::write(dir.join("zfs.ko"), zfs).unwrap();
+        // nothing modinfo could go by
+        std::fs::write(dir.join("blob.ko"), blob).unwrap();
+        std::fs::write(dir.join("same.ko"), "same").unwrap();
+        systems.push(root.path().join(name));
+    }
+    // stands in for modinfo, the fake modules are their own modinfo output
+    let modinfo = root.path().join("modinfo");
+    std::fs::write(&modinfo, "#!/bin/sh\ncat \"$1\"\n").unwrap();
+    std::fs::set_permissions(
+        &modinfo,
+        std::os::unix::fs::PermissionsExt::from_mode(0o755),
+    )
+    .unwrap();
+
+    assert_eq!(
+        changed_modules(
+            &systems[0],
+            &systems[1],
+            &["xone", "zfs", "blob", "same", "missing"],
+            Some(&modinfo.to_string_lossy())
+        )
+        .unwrap(),
+        Changed {
+            versioned: vec!["xone".to_string()],
+            unversioned: vec!["blob".to_string()],
+        }
+    );
+}
+
+#[test]
+fn many_changed_modules_collapse_into_a_count() {
+    let finding = Finding {
+        label: "Reboot".to_string(),
+        items: ["kernel", "zfs", "xone", "module changed"]
+            .map(str::to_string)
+            .to_vec(),
+    };
+    let modules = ["zfs", "xone", "v4l2loopback"];
+
+    assert_eq!(
+        collapse(&finding, &modules, 2).items,
+        ["kernel", "3 kernel modules changed"]
+    );
+    assert_eq!(collapse(&finding, &modules, 3), finding);
+}
+
+/// A bare little endian ELF64 file with nothing but a `.modinfo` section holding `modinfo`.
+fn elf(modinfo: &[u8]) -> Vec<u8> {
+    let names = b"\0.shstrtab\0.modinfo\0";
+    let mut elf = vec![0; 64];
+    elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    let names_at = elf.len() as u64;
+    elf.extend_from_slice(names);
+    let modinfo_at = elf.len() as u64;
+    elf.extend_from_slice(modinfo);
+    let headers_at = elf.len() as u64;
+
+    elf[0x28..0x30].copy_from_slice(&headers_at.to_le_bytes());
+    elf[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+    elf[0x3e..0x40].copy_from_slice(&1u16.to_le_bytes());
+    for (name, offset, size) in [
+        (0u32, 0u64, 0u64),
+        (1, names_at, names.len() as u64),
+        (11, modinfo_at, modinfo.len() as u64),
+    ] {
+        let mut header = [0; 64];
+        header[..4].copy_from_slice(&name.to_le_bytes());
+        header[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+        header[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+        elf.extend_from_slice(&header);
+    }
+    elf
+}
+
+#[test]
+fn modinfo_is_read_from_the_module_itself() {
+    let module = elf(b"version=#VERSION#\0srcversion=8F2E1\0vermagic=6.6.30 SMP\0");
+    let info = elf_modinfo(&module).unwrap();
+
+    assert_eq!(
+        info,
+        "version: #VERSION#\nsrcversion: 8F2E1\nvermagic: 6.6.30 SMP\n"
+    );
+    assert_eq!(identity(&info).as_deref(), Some("srcversion 8F2E1"));
+    // compressed modules and anything cut short aren't read
+    assert_eq!(elf_modinfo(b"\xfd7zXZ\0"), None);
+    assert_eq!(elf_modinfo(&module[..100]), None);
+}
+
+#[test]
+fn modules_are_compared_without_modinfo() {
+    let root = tempfile::tempdir().unwrap();
+    let mut systems = Vec::new();
+    for (name, xone, blob) in [
+        ("booted", elf(b"srcversion=AAA\0"), "old"),
+        ("current", elf(b"srcversion=BBB\0"), "new"),
+    ] {
+        let dir = root
+            .path()
+            .join(name)
+            .join("kernel-modules/lib/modules/6.6.30/extra");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.with_file_name("modules.dep"), "").unwrap();
+        std::fs::write(dir.join("xone.ko"), xone).unwrap();
+        // compressed, so only modinfo could read it
+        std::fs::write(dir.join("blob.ko.xz"), blob).unwrap();
+        std::fs::write(dir.join("same.ko.xz"), "same").unwrap();
+        systems.push(root.path().join(name));
+    }
+    let expected = Changed {
+        versioned: vec!["xone".to_string()],
+        unversioned: vec!["blob".to_string()],
+    };
+    let names = ["xone", "blob", "same"];
+
+    assert_eq!(
+        changed_modules(&systems[0], &systems[1], &names, None).unwrap(),
+        expected
+    );
+    // a missing modinfo is no different from not running it
+    assert_eq!(
+        changed_modules(
+            &systems[0],
+            &systems[1],
+            &names,
+            Some("/nonexistent/modinfo")
+        )
+        .unwrap(),
+        expected
+    );
+}
@@ -0,0 +1,51 @@
+use i3status_nix_update_widget::nix_version::{daemon_binary, older_than, version_of};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+#[test]
+fn version_comes_from_the_store_path() {
+    assert_eq!(
+        version_of(Path::new(
+            "/nix/store/0123456789abcdfghijklmnpqrsvwxyz-nix-2.18.1/bin/nix"
+        )),
+        Some("2.18.1".to_string())
+    );
+    assert_eq!(
+        version_of(Path::new(
+            "/nix/store/0123456789abcdfghijklmnpqrsvwxyz-lix-2.90.0-rc1/bin/nix"
+        )),
+        Some("2.90.0-rc1".to_string())
+    );
+    assert_eq!(version_of(Path::new("/usr/bin/nix")), None);
+}
+
+#[test]
+fn versions_compare_numerically() {
+    assert!(older_than("2.9.2", "2.18"));
+    assert!(older_than("2.18.1", "2.18.2"));
+    assert!(!older_than("2.18.1", "2.18"));
+    assert!(!older_than("2.24.0pre20240101", "2.24"));
+    assert!(!older_than("2.90.0-rc1", "2.24"));
+}
+
+#[test]
+fn daemon_is_found_by_name() {
+    let proc = tempfile::tempdir().unwrap();
+    for (pid, comm, exe) in [
+        ("1", "systemd\n", "/lib/systemd/systemd"),
+        ("812", "nix-daemon\n", "/nix/store/aaa-nix-2.18.1/bin/nix"),
+    ] {
+        let dir = proc.path().join(pid);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("comm"), comm).unwrap();
+        symlink(exe, dir.join("exe")).unwrap();
+    }
+
+    assert_eq!(
+        daemon_binary(proc.path()).unwrap(),
+        Some("/nix/store/aaa-nix-2.18.1/bin/nix".into())
+    );
+
+    std::fs::remove_dir_all(proc.path().join("812")).unwrap();
+    assert_eq!(daemon_binary(proc.path()).unwrap(), None);
+}
@@ -0,0 +1,153 @@
+use i3status_nix_update_widget::error::Error;
+use i3status_nix_update_widget::otlp::{body, export, Ids, TIMEOUT};
+use i3status_nix_update_widget::Span;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+fn ids() -> Ids {
+    Ids {
+        trace: "0af7651916cd43dd8448eb211c80319c".to_string(),
+        root: "b7ad6b7169203331".to_string(),
+        spans: vec![
+            "00f067aa0ba902b7".to_string(),
+            "00f067aa0ba902b8".to_string(),
+        ],
+    }
+}
+
+fn spans() -> Vec<Span> {
+    vec![
+        Span {
+            check: "reboot".to_string(),
+            start: 1_000,
+            duration: Duration::from_nanos(500),
+            error: None,
+        },
+        Span {
+            check: "failed".to_string(),
+            start: 1_600,
+            duration: Duration::from_nanos(900),
+            error: Some("systemctl failed: no bus".to_string()),
+        },
+    ]
+}
+
+#[test]
+fn checks_are_children_of_one_span_for_the_run() {
+    let body = body(&spans(), "web1", &ids());
+    let resource = &body["resourceSpans"][0];
+    assert_eq!(
+        resource["resource"]["attributes"][2],
+        serde_json::json!({"key": "host.name", "value": {"stringValue": "web1"}})
+    );
+
+    let exported = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+    assert_eq!(exported.len(), 3);
+    assert_eq!(exported[0]["name"], "checks");
+    assert_eq!(exported[0]["startTimeUnixNano"], "1000");
+    assert_eq!(exported[0]["endTimeUnixNano"], "2500");
+    assert!(exported[0].get("parentSpanId").is_none());
+
+    assert_eq!(exported[1]["name"], "reboot");
+    assert_eq!(exported[1]["parentSpanId"], "b7ad6b7169203331");
+    assert_eq!(exported[1]["endTimeUnixNano"], "1500");
+    assert_eq!(exported[1]["status"], serde_json::json!({"code": 1}));
+    assert_eq!(
+        exported[2]["status"],
+        serde_json::json!({"code": 2, "message": "systemctl failed: no bus"})
+    );
+}
+
+#[test]
+fn random_ids_have_the_right_length() {
+    let ids = Ids::random(2);
+    assert_eq!(ids.trace.len(), 32);
+    assert_eq!(ids.root.len(), 16);
+    assert_eq!(ids.spans.len(), 2);
+    assert_ne!(ids.spans[0], ids.spans[1]);
+}
+
+#[test]
+fn traces_are_posted_to_the_collector() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/otlp/", listener.local_addr().unwrap());
+    let collector = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        // read until the whole body is in, the client waits for the answer before closing
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap();
+                if body.len() == length.parse::<usize>().unwrap() {
+                    break;
+                }
+            }
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    export(&endpoint, &body(&spans(), "web1", &ids()), TIMEOUT).unwrap();
+    let request = collector.join().unwrap();
+    assert!(request.starts_with("POST /otlp/v1/traces HTTP/1.1\r\n"));
+    assert!(request.contains("Content-Type: application/json\r\n"));
+}
+
+#[test]
+fn only_plain_http_is_spoken() {
+    assert!(matches!(
+        export("https://collector:4318", &serde_json::json!({}), TIMEOUT),
+        Err(Error::Config(_))
+    ));
+}
+
+#[test]
+fn a_collector_that_never_answers_is_given_up_on() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    // accepted, read and then left hanging
+    let collector = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let _ = stream.read(&mut [0; 4096]);
+        std::thread::sleep(Duration::from_millis(500));
+    });
+
+    let started = Instant::now();
+    let result = export(
+        &endpoint,
+        &serde_json::json!({}),
+        Duration::from_millis(100),
+    );
+    assert!(matches!(result, Err(Error::Network { .. })));
+    assert!(started.elapsed() < Duration::from_millis(400));
+    collector.join().unwrap();
+}
+
+#[test]
+fn refused_exports_are_network_errors() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let collector = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let _ = stream.read(&mut [0; 4096]);
+        stream
+            .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+    });
+
+    let err = export(&endpoint, &serde_json::json!({}), TIMEOUT).unwrap_err();
+    assert!(matches!(err, Error::Network { .. }));
+    assert!(err
+        .to_string()
+        .ends_with("failed: HTTP/1.1 400 Bad Request"));
+    collector.join().unwrap();
+}
@@ -0,0 +1,60 @@
+use i3status_nix_update_widget::processes::{
+    maps_deleted_store_path, owned_processes_using_deleted_store_paths,
+    processes_using_deleted_store_paths,
+};
+
+const LIVE_MAPS: &str = "\
+55d0c0a00000-55d0c0a28000 r--p 00000000 00:1f 123 /nix/store/aaa-firefox-120.0/bin/.firefox-wrapped
+7f1c2c000000-7f1c2c021000 rw-p 00000000 00:00 0
+7f1c2d000000-7f1c2d1c6000 r-xp 00028000 00:1f 456 /nix/store/bbb-glibc-2.38/lib/libc.so.6
+7ffd1e5f0000-7ffd1e611000 rw-p 00000000 00:00 0 [stack]
+";
+
+const DELETED_MAPS: &str = "\
+55d0c0a00000-55d0c0a28000 r--p 00000000 00:1f 123 /nix/store/aaa-firefox-120.0/bin/.firefox-wrapped
+7f1c2d000000-7f1c2d1c6000 r-xp 00028000 00:1f 456 /nix/store/ccc-glibc-2.37/lib/libc.so.6 (deleted)
+";
+
+#[test]
+fn detects_deleted_store_mappings() {
+    assert!(!maps_deleted_store_path(LIVE_MAPS));
+    assert!(maps_deleted_store_path(DELETED_MAPS));
+    assert!(!maps_deleted_store_path(
+        "7f1c2c000000-7f1c2c021000 rw-s 00000000 00:01 7 /memfd:wayland (deleted)\n"
+    ));
+}
+
+#[test]
+fn scans_proc() {
+    let proc = tempfile::tempdir().unwrap();
+    for (pid, comm, maps) in [
+        ("1", "systemd", LIVE_MAPS),
+        ("42", "firefox", DELETED_MAPS),
+        ("43", "firefox", DELETED_MAPS),
+        ("100", "sway", DELETED_MAPS),
+    ] {
+        let dir = proc.path().join(pid);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        std::fs::write(dir.join("maps"), maps).unwrap();
+    }
+    std::fs::create_dir(proc.path().join("self")).unwrap();
+    // a process that exited between listing and reading
+    std::fs::create_dir(proc.path().join("77")).unwrap();
+
+    assert_eq!(
+        processes_using_deleted_store_paths(proc.path()).unwrap(),
+        ["firefox", "sway"]
+    );
+
+    let uid = std::os::unix::fs::MetadataExt::uid(&proc.path().metadata().unwrap());
+    assert_eq!(
+        owned_processes_using_deleted_store_paths(proc.path(), uid).unwrap(),
+        ["firefox", "sway"]
+    );
+    assert!(
+        owned_processes_using_deleted_store_paths(proc.path(), uid + 1)
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,48 @@
+use i3status_nix_update_widget::project::{pinned_nixpkgs, project_dir};
+use std::path::Path;
+
+#[test]
+fn direnv_dir_wins_over_the_working_directory() {
+    assert_eq!(
+        project_dir(Some("-/home/me/work"), Path::new("/home/me/work/src")),
+        Path::new("/home/me/work")
+    );
+    assert_eq!(project_dir(None, Path::new("/tmp")), Path::new("/tmp"));
+    assert_eq!(project_dir(Some(""), Path::new("/tmp")), Path::new("/tmp"));
+}
+
+#[test]
+fn follows_the_root_input_to_the_nixpkgs_node() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("flake.lock"),
+        r#"{
+            "nodes": {
+                "nixpkgs_2": { "locked": { "lastModified": 1700000000 } },
+                "nixpkgs": { "locked": { "lastModified": 1600000000 } },
+                "root": { "inputs": { "nixpkgs": "nixpkgs_2" } }
+            },
+            "root": "root",
+            "version": 7
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(pinned_nixpkgs(dir.path()).unwrap(), Some(1700000000));
+}
+
+#[test]
+fn no_lock_or_no_nixpkgs_is_nothing_to_report() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_eq!(pinned_nixpkgs(dir.path()).unwrap(), None);
+
+    std::fs::write(
+        dir.path().join("flake.lock"),
+        r#"{ "nodes": { "root": { "inputs": {} } } }"#,
+    )
+    .unwrap();
+    assert_eq!(pinned_nixpkgs(dir.path()).unwrap(), None);
+
+    std::fs::write(dir.path().join("flake.lock"), "not json").unwrap();
+    assert!(pinned_nixpkgs(dir.path()).is_err());
+}
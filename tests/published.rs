@@ -0,0 +1,37 @@
+use i3status_nix_update_widget::published::Published;
+use i3status_nix_update_widget::state_dir::StateDir;
+use i3status_nix_update_widget::Finding;
+
+#[test]
+fn published_findings_round_trip() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = StateDir::at(root.path());
+
+    let published = Published {
+        timestamp: 1_700_000_000,
+        findings: vec![Finding {
+            label: "Restart".to_string(),
+            items: vec!["firefox".to_string(), "sway".to_string()],
+        }],
+    };
+    dir.write(&published).unwrap();
+
+    assert_eq!(dir.read::<Published>(), Some(published));
+}
+
+#[test]
+fn old_results_are_called_stale() {
+    let published = Published {
+        timestamp: 1_700_000_000,
+        findings: vec![],
+    };
+
+    assert_eq!(published.stale(1_700_000_000 + 5 * 3600, 6), None);
+    assert_eq!(
+        published.stale(1_700_000_000 + 30 * 3600, 6),
+        Some(Finding {
+            label: "Published stale".to_string(),
+            items: vec!["30h old".to_string()],
+        })
+    );
+}
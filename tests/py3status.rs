@@ -0,0 +1,32 @@
+use i3status_nix_update_widget::py3status::composite;
+use i3status_nix_update_widget::theme;
+use i3status_nix_update_widget::{BarCommand, State};
+
+fn render(state: State) -> String {
+    let command = BarCommand {
+        icon: "cogs".to_string(),
+        state,
+        text: "Age: 9".to_string(),
+    };
+    serde_json::to_string(&composite(&command, &theme::DEFAULT)).unwrap()
+}
+
+#[test]
+fn composite_has_colour_and_urgency() {
+    assert_eq!(
+        render(State::Good),
+        r##"[{"full_text":"Age: 9","color":"#00FF00","urgent":false}]"##
+    );
+    assert_eq!(
+        render(State::Critical),
+        r##"[{"full_text":"Age: 9","color":"#FF0000","urgent":true}]"##
+    );
+}
+
+#[test]
+fn info_uses_the_default_colour() {
+    assert_eq!(
+        render(State::Info),
+        r#"[{"full_text":"Age: 9","urgent":false}]"#
+    );
+}
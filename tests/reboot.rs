@@ -0,0 +1,239 @@
+use i3status_nix_update_widget::error::Error;
+use i3status_nix_update_widget::reboot::{
+    changed_boot_components, changed_probes, changed_units, read_reboot_required,
+    sync_reboot_required, Probe,
+};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+fn system(root: &Path, name: &str, kernel: &str, initrd: &str) -> std::path::PathBuf {
+    let system = root.join(name);
+    std::fs::create_dir_all(&system).unwrap();
+    for (link, target) in [("kernel", kernel), ("initrd", initrd)] {
+        let target = root.join("store").join(target);
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(&target, target.to_string_lossy().as_bytes()).unwrap();
+        symlink(target, system.join(link)).unwrap();
+    }
+    system
+}
+
+#[test]
+fn same_generation_needs_no_reboot() {
+    let root = tempfile::tempdir().unwrap();
+    let booted = system(
+        root.path(),
+        "booted",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+    let current = system(
+        root.path(),
+        "current",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+
+    assert!(changed_boot_components(&booted, &current)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn changed_initrd_is_reported() {
+    let root = tempfile::tempdir().unwrap();
+    let booted = system(
+        root.path(),
+        "booted",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+    let current = system(
+        root.path(),
+        "current",
+        "aaa-linux/bzImage",
+        "ccc-initrd/initrd",
+    );
+
+    assert_eq!(
+        changed_boot_components(&booted, &current).unwrap(),
+        ["initrd"]
+    );
+}
+
+#[test]
+fn changed_kernel_is_reported() {
+    let root = tempfile::tempdir().unwrap();
+    let booted = system(
+        root.path(),
+        "booted",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+    let current = system(
+        root.path(),
+        "current",
+        "ddd-linux/bzImage",
+        "eee-initrd/initrd",
+    );
+
+    assert_eq!(
+        changed_boot_components(&booted, &current).unwrap(),
+        ["kernel", "initrd"]
+    );
+}
+
+#[test]
+fn missing_system_is_an_error() {
+    let root = tempfile::tempdir().unwrap();
+    let current = system(
+        root.path(),
+        "current",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+
+    assert!(matches!(
+        changed_boot_components(&root.path().join("booted"), &current),
+        Err(Error::SystemProfile { .. })
+    ));
+}
+
+#[test]
+fn reboot_required_files_round_trip() {
+    let run = tempfile::tempdir().unwrap();
+    let changed = ["kernel".to_string(), "initrd".to_string()];
+
+    sync_reboot_required(run.path(), &changed).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(run.path().join("reboot-required.pkgs")).unwrap(),
+        "kernel\ninitrd\n"
+    );
+    assert_eq!(read_reboot_required(run.path()).unwrap(), changed);
+
+    sync_reboot_required(run.path(), &[]).unwrap();
+    assert!(!run.path().join("reboot-required").exists());
+    assert!(!run.path().join("reboot-required.pkgs").exists());
+    assert!(read_reboot_required(run.path()).unwrap().is_empty());
+
+    // nothing to remove is fine too
+    sync_reboot_required(run.path(), &[]).unwrap();
+}
+
+#[test]
+fn marker_without_package_list_means_the_system() {
+    let run = tempfile::tempdir().unwrap();
+    std::fs::write(run.path().join("reboot-required"), "").unwrap();
+
+    assert_eq!(read_reboot_required(run.path()).unwrap(), ["system"]);
+}
+
+#[test]
+fn reasons_in_the_marker_are_read() {
+    let run = tempfile::tempdir().unwrap();
+    std::fs::write(
+        run.path().join("reboot-required"),
+        "kernel: 6.6.20 -> 6.6.21\nsystemd: 254.6 -> 255.2\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        read_reboot_required(run.path()).unwrap(),
+        ["kernel: 6.6.20 -> 6.6.21", "systemd: 254.6 -> 255.2"]
+    );
+
+    std::fs::write(
+        run.path().join("reboot-required"),
+        "*** System restart required ***\n",
+    )
+    .unwrap();
+    assert_eq!(read_reboot_required(run.path()).unwrap(), ["system"]);
+}
+
+#[test]
+fn probes_report_what_differs() {
+    let root = tempfile::tempdir().unwrap();
+    let booted = system(
+        root.path(),
+        "booted",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+    let current = system(
+        root.path(),
+        "current",
+        "aaa-linux/bzImage",
+        "bbb-initrd/initrd",
+    );
+    std::fs::write(booted.join("firmware"), "old").unwrap();
+    std::fs::write(current.join("firmware"), "new").unwrap();
+
+    // leaked so they can be used where the config would have static strings
+    let path = |path: std::path::PathBuf| -> &'static str {
+        Box::leak(path.to_string_lossy().into_owned().into_boxed_str())
+    };
+    let probes = [
+        Probe::Paths {
+            name: "firmware",
+            booted: path(booted.join("firmware")),
+            current: path(current.join("firmware")),
+        },
+        Probe::Paths {
+            name: "kernel",
+            booted: path(booted.join("kernel")),
+            current: path(current.join("kernel")),
+        },
+        Probe::Paths {
+            name: "missing",
+            booted: path(booted.join("missing")),
+            current: path(current.join("missing")),
+        },
+        // the system paths differ, so echo prints something different for each
+        Probe::Command {
+            name: "echo",
+            command: "echo",
+        },
+        Probe::Command {
+            name: "true",
+            command: "true",
+        },
+    ];
+
+    assert_eq!(
+        changed_probes(&probes, &booted, &current, false).unwrap(),
+        ["firmware", "echo"]
+    );
+    assert_eq!(
+        changed_probes(&probes, &booted, &current, true).unwrap(),
+        ["firmware"]
+    );
+}
+
+#[test]
+fn added_and_removed_units_are_reported() {
+    let root = tempfile::tempdir().unwrap();
+    for (name, units) in [
+        (
+            "booted",
+            ["sshd.service", "old.timer", "multi-user.target.wants"],
+        ),
+        ("current", ["sshd.service", "new.service", "sshd.service.d"]),
+    ] {
+        let dir = root.path().join(name).join("etc/systemd/system");
+        std::fs::create_dir_all(&dir).unwrap();
+        for unit in units {
+            std::fs::write(dir.join(unit), "").unwrap();
+        }
+    }
+
+    assert_eq!(
+        changed_units(&root.path().join("booted"), &root.path().join("current")).unwrap(),
+        ["+new.service", "-old.timer"]
+    );
+    // not NixOS
+    assert!(
+        changed_units(&root.path().join("x"), &root.path().join("y"))
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,14 @@
+use i3status_nix_update_widget::reboot_pending::{advance, RebootPending};
+
+#[test]
+fn pending_since_the_first_time_it_was_needed() {
+    let first = advance(None, true, 100);
+    assert_eq!(first, RebootPending { since: Some(100) });
+
+    let later = advance(Some(&first), true, 500);
+    assert_eq!(later, RebootPending { since: Some(100) });
+
+    let rebooted = advance(Some(&later), false, 600);
+    assert_eq!(rebooted, RebootPending { since: None });
+    assert_eq!(advance(Some(&rebooted), true, 700).since, Some(700));
+}
@@ -0,0 +1,41 @@
+use i3status_nix_update_widget::registry::pins;
+
+#[test]
+fn only_pinned_entries_have_a_date() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("registry.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "flakes": [
+                {
+                    "from": { "id": "nixpkgs", "type": "indirect" },
+                    "to": {
+                        "lastModified": 1700000000,
+                        "owner": "NixOS",
+                        "repo": "nixpkgs",
+                        "rev": "0123456789abcdef0123456789abcdef01234567",
+                        "type": "github"
+                    }
+                },
+                {
+                    "from": { "id": "home-manager", "type": "indirect" },
+                    "to": { "owner": "nix-community", "repo": "home-manager", "type": "github" }
+                }
+            ],
+            "version": 2
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        pins(&path).unwrap(),
+        vec![("nixpkgs".to_string(), 1700000000)]
+    );
+}
+
+#[test]
+fn missing_registry_has_no_pins() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(pins(&dir.path().join("registry.json")).unwrap().is_empty());
+}
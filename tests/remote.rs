@@ -0,0 +1,79 @@
+use i3status_nix_update_widget::remote::{check_all, expand, ssh_config_hosts};
+use i3status_nix_update_widget::State;
+use std::time::Duration;
+
+const SSH_CONFIG: &str = "\
+Host web1 web2
+    HostName 10.0.0.1
+Host db1
+host *.internal !bastion
+Match all
+";
+
+#[test]
+fn concrete_hosts_come_out_of_the_ssh_config() {
+    assert_eq!(ssh_config_hosts(SSH_CONFIG), ["web1", "web2", "db1"]);
+}
+
+#[test]
+fn globs_and_files_expand_to_hosts() {
+    let known = ssh_config_hosts(SSH_CONFIG);
+    let specs = ["web*", "@fleet", "web1", "other"].map(str::to_string);
+    let hosts = expand(&specs, &known, |path| {
+        assert_eq!(path, "fleet");
+        Ok("# the databases\ndb1\ndb2 # spare\n\n".to_string())
+    })
+    .unwrap();
+
+    assert_eq!(hosts, ["web1", "web2", "db1", "db2", "other"]);
+}
+
+#[test]
+fn hosts_are_asked_in_parallel_with_a_timeout() {
+    let hosts = ["fast", "slow", "broken"].map(str::to_string);
+    // stands in for ssh to an agent: answers for fast, hangs for slow and fails for anything else
+    let script = r#"case "$0" in
+        fast) read -r request
+            echo '{"protocol":1,"version":"0.1.0","status":{"state":"Warning","cause":"Reboot","text":"Reboot: kernel"}}' ;;
+        slow) sleep 10 ;;
+        *) exit 1 ;;
+    esac"#;
+    let started = std::time::Instant::now();
+    let statuses = check_all(
+        &hosts,
+        &["sh", "-c", script, "{host}"],
+        3,
+        Duration::from_millis(500),
+    );
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        statuses
+            .iter()
+            .map(|status| (status.host.as_str(), status.state, status.cause.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("fast", State::Warning, "Reboot"),
+            ("slow", State::Critical, "unreadable"),
+            ("broken", State::Critical, "unreadable"),
+        ]
+    );
+    assert_eq!(statuses[1].text, "timed out after 0s");
+}
+
+#[test]
+fn hosts_that_look_like_options_are_refused() {
+    let hosts = ["-oProxyCommand=touch pwned".to_string()];
+    let statuses = check_all(
+        &hosts,
+        &["sh", "-c", "exit 0", "{host}"],
+        1,
+        Duration::from_secs(5),
+    );
+
+    assert_eq!(statuses[0].state, State::Critical);
+    assert_eq!(
+        statuses[0].text,
+        "-oProxyCommand=touch pwned isn't a host name"
+    );
+}
@@ -0,0 +1,351 @@
+use i3status_nix_update_widget::{
+    action, i3bar, lock_age, merge_fields, recommendation, rename_fields, state_for_age, theme,
+    truncate, write_age_only, Action, BarCommand, Finding, FixedClock, Simulation, State,
+    StateNames, Style, Thresholds, Tooltip, FORMATS,
+};
+
+fn restart(items: &[&str]) -> Finding {
+    Finding {
+        label: "Restart".to_string(),
+        items: items.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+#[test]
+fn few_items_are_listed_inline() {
+    assert_eq!(
+        restart(&["firefox", "sway"]).render(3),
+        "Restart: firefox, sway"
+    );
+    assert_eq!(restart(&["a", "b", "c"]).render(3), "Restart: a, b, c");
+}
+
+#[test]
+fn many_items_are_counted() {
+    assert_eq!(restart(&["a", "b", "c", "d"]).render(3), "Restart: 4");
+    assert_eq!(restart(&["a"]).render(0), "Restart: 1");
+}
+
+#[test]
+fn details_list_every_item() {
+    assert_eq!(
+        restart(&["a", "b", "c", "d"]).render_details(),
+        "Restart: a\nRestart: b\nRestart: c\nRestart: d\n"
+    );
+}
+
+#[test]
+fn truncates_to_max_width() {
+    let text = "Age: 12 Reboot: nvidia, xone, v4l2loopback";
+    assert_eq!(truncate(text, 0), text);
+    assert_eq!(truncate(text, 100), text);
+    assert_eq!(truncate(text, text.len()), text);
+    assert_eq!(truncate(text, 16), "Age: 12 Reboot:…");
+    assert_eq!(truncate(text, 1), "…");
+}
+
+#[test]
+fn truncates_by_characters_not_bytes() {
+    assert_eq!(truncate("Älter: 12 Tage", 6), "Älter…");
+}
+
+#[test]
+fn merges_extra_fields() {
+    let block = serde_json::json!({ "text": "Age: 9", "state": "Good" });
+    let merged = merge_fields(block, r#"{"name":"nix-update","state":"Info"}"#).unwrap();
+    assert_eq!(
+        merged,
+        serde_json::json!({ "text": "Age: 9", "state": "Info", "name": "nix-update" })
+    );
+}
+
+#[test]
+fn extra_fields_must_be_an_object() {
+    let block = serde_json::json!({ "text": "Age: 9" });
+    assert!(merge_fields(block.clone(), "[1, 2]").is_err());
+    assert_eq!(merge_fields(block.clone(), "{}").unwrap(), block);
+}
+
+#[test]
+fn simulation_has_an_age_and_findings() {
+    let simulation =
+        Simulation::parse("age=20,reboot=kernel+initrd,boot-stale=entry.conf").unwrap();
+    assert_eq!(simulation.age, Some(20));
+    assert_eq!(
+        simulation.findings,
+        [
+            Finding {
+                label: "Reboot".to_string(),
+                items: vec!["kernel".to_string(), "initrd".to_string()],
+            },
+            Finding {
+                label: "Boot stale".to_string(),
+                items: vec!["entry.conf".to_string()],
+            },
+        ]
+    );
+
+    assert_eq!(Simulation::parse("").unwrap().age, None);
+    assert!(Simulation::parse("age=old").is_err());
+    assert!(Simulation::parse("reboot").is_err());
+}
+
+#[test]
+fn fields_are_renamed() {
+    let block = serde_json::json!({"icon": "cogs", "text": "Age: 9"});
+    assert_eq!(
+        rename_fields(block, &[("text", "full_text"), ("missing", "other")]),
+        serde_json::json!({"icon": "cogs", "full_text": "Age: 9"})
+    );
+}
+
+#[test]
+fn findings_have_reason_codes() {
+    use i3status_nix_update_widget::{age_reason, finding_reasons, State};
+
+    let reboot = Finding {
+        label: "Reboot".to_string(),
+        items: vec!["kernel".to_string(), "firmware".to_string()],
+    };
+    assert_eq!(
+        finding_reasons(&reboot),
+        ["KERNEL_MISMATCH", "REBOOT_NEEDED"]
+    );
+    assert_eq!(
+        finding_reasons(&restart(&["firefox", "sshd"])),
+        ["SERVICE_RESTART_NEEDED"]
+    );
+    assert_eq!(age_reason(State::Critical), Some("AGE_OVER_CRITICAL"));
+    assert_eq!(age_reason(State::Good), None);
+}
+
+#[test]
+fn action_covers_every_finding() {
+    let finding = |label: &str, items: &[&str]| Finding {
+        label: label.to_string(),
+        items: items.iter().map(|item| item.to_string()).collect(),
+    };
+    let session = ["firefox".to_string()];
+
+    assert_eq!(action(&[], &session), None);
+    assert_eq!(
+        action(&[finding("Restart", &["firefox"])], &session),
+        Some(Action::Relog)
+    );
+    assert_eq!(
+        action(&[finding("Restart", &["firefox", "sshd"])], &session),
+        Some(Action::Restart)
+    );
+    assert_eq!(
+        action(
+            &[
+                finding("Reboot", &["kernel"]),
+                finding("Restart", &["firefox"])
+            ],
+            &session
+        ),
+        Some(Action::Reboot)
+    );
+    assert_eq!(
+        action(&[finding("Failed", &["backup.timer"])], &session),
+        None
+    );
+}
+
+#[test]
+fn recommendation_puts_the_update_first() {
+    assert_eq!(
+        recommendation(State::Critical, true, Some(Action::Reboot)),
+        "run flake update"
+    );
+    assert_eq!(
+        recommendation(State::Info, true, Some(Action::Reboot)),
+        "switch pending config"
+    );
+    assert_eq!(
+        recommendation(State::Good, false, Some(Action::Relog)),
+        "relog"
+    );
+    assert_eq!(recommendation(State::Good, false, None), "all good");
+}
+
+const CUSTOM: StateNames = StateNames::Custom {
+    info: "meh",
+    good: "fine",
+    warning: "hmm",
+    critical: "bad",
+};
+
+fn style(state_names: &StateNames) -> Style<'_> {
+    Style {
+        state_names,
+        i3bar_layout: &i3bar::Layout {
+            name: None,
+            instance: None,
+            min_width: None,
+            align: None,
+            separator_block_width: None,
+        },
+        theme: &theme::DEFAULT,
+        i3bar_colors: false,
+        field_names: &[],
+        extra_fields: "{}",
+    }
+}
+
+fn sample(state: State) -> (BarCommand, Tooltip<'static>) {
+    let code = BarCommand {
+        icon: "update".to_string(),
+        state,
+        text: "Age: 9".to_string(),
+    };
+    let tooltip = Tooltip {
+        findings: &[],
+        updated: None,
+        percentage: 60,
+    };
+    (code, tooltip)
+}
+
+#[test]
+fn preview_shows_every_state_in_every_format_by_its_configured_name() {
+    let lines = style(&CUSTOM).preview(sample).unwrap();
+    assert_eq!(lines.len(), 4 * FORMATS.len());
+
+    let labels = lines
+        .iter()
+        .map(|line| line.split_once(':').unwrap().0)
+        .collect::<Vec<_>>();
+    for (at, name) in ["fine", "meh", "hmm", "bad"].iter().enumerate() {
+        for (format, label) in FORMATS.iter().zip(&labels[at * FORMATS.len()..]) {
+            assert_eq!(*label, format!("{format} {name}"));
+        }
+    }
+    // and the block itself says so too
+    assert!(lines[12].ends_with(r#"{"icon":"update","state":"bad","text":"Age: 9"}"#));
+
+    let numeric = style(&StateNames::Numeric).preview(sample).unwrap();
+    assert!(numeric[12].starts_with("i3status-rs 3: "));
+}
+
+#[test]
+fn age_only_block_is_the_full_paths_byte_for_byte() {
+    use chrono::TimeZone;
+    let thresholds = Thresholds {
+        good: 3,
+        update: 7,
+        out_of_date: 14,
+    };
+    let locked = 1_700_000_000;
+    let tooltip = Tooltip {
+        findings: &[],
+        updated: None,
+        percentage: 0,
+    };
+
+    // good, info, warning, critical, and a lock from the future
+    for (days, state) in [
+        (1, State::Good),
+        (5, State::Info),
+        (7, State::Warning),
+        (20, State::Critical),
+        (-2, State::Good),
+    ] {
+        let clock = FixedClock(
+            chrono::Utc.timestamp_opt(locked, 0).unwrap() + chrono::Duration::days(days),
+        );
+        let age = lock_age(&clock, locked).unwrap();
+        assert_eq!(state_for_age(age.days, &thresholds), state);
+
+        let mut fast = Vec::new();
+        write_age_only(&mut fast, "update", &age, &thresholds, true).unwrap();
+
+        let mut text = format!("Age: {}", age.days);
+        if age.clock_skew {
+            text.push_str(" (clock skew?)");
+        }
+        let code = BarCommand {
+            icon: "update".to_string(),
+            state,
+            text,
+        };
+        let full = style(&StateNames::Variant)
+            .render("i3status-rs", &code, &tooltip)
+            .unwrap();
+        assert_eq!(String::from_utf8(fast).unwrap(), format!("{full}\n"));
+    }
+}
+
+#[test]
+fn reasons_come_once_each_in_order() {
+    use i3status_nix_update_widget::reasons;
+
+    let states = [
+        ("age".to_string(), State::Warning),
+        ("reboot".to_string(), State::Warning),
+        ("dotfiles".to_string(), State::Warning),
+        ("work".to_string(), State::Good),
+    ];
+    let reboot = Finding {
+        label: "Reboot".to_string(),
+        items: vec!["kernel".to_string(), "firmware".to_string()],
+    };
+    let findings = [
+        reboot.clone(),
+        restart(&["sshd"]),
+        Finding {
+            label: "Reboot required".to_string(),
+            items: vec!["kernel".to_string(), "microcode".to_string()],
+        },
+        restart(&["firefox"]),
+    ];
+
+    assert_eq!(
+        reasons(&states, &findings),
+        [
+            "AGE_OVER_WARNING",
+            "REBOOT_OVERDUE",
+            "KERNEL_MISMATCH",
+            "REBOOT_NEEDED",
+            "SERVICE_RESTART_NEEDED"
+        ]
+    );
+    assert!(reasons(&[("age".to_string(), State::Good)], &[]).is_empty());
+}
+
+#[test]
+fn details_json_has_the_percentage() {
+    let state = tempfile::tempdir().unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_i3status-nix-update-widget"))
+        .args(["--details", "--json", "--no-exec"])
+        .env("XDG_STATE_HOME", state.path())
+        .output()
+        .unwrap();
+    let details: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(details["percentage"]
+        .as_u64()
+        .is_some_and(|percentage| percentage <= 100));
+    assert!(details["reasons"].is_array());
+}
+
+#[test]
+fn only_critical_blocks_are_urgent_and_only_where_the_format_has_it() {
+    for state in [State::Good, State::Info, State::Warning, State::Critical] {
+        let (code, tooltip) = sample(state);
+        for format in FORMATS {
+            let block = style(&StateNames::Variant)
+                .render(format, &code, &tooltip)
+                .unwrap();
+            let block = block.as_array().map_or(&block, |parts| &parts[0]);
+            let urgent = block.get("urgent");
+            match *format {
+                "i3bar" | "py3status" => {
+                    assert_eq!(urgent, Some(&serde_json::json!(state == State::Critical)))
+                }
+                _ => assert_eq!(urgent, None, "{format}"),
+            }
+            assert_eq!(block.get("blink"), None);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use i3status_nix_update_widget::rules::{apply, at_least, glob_match, state, Rule};
+use i3status_nix_update_widget::{worst, Finding, State};
+
+const RULES: &[Rule] = &[
+    Rule {
+        pattern: "v4l2loopback",
+        state: None,
+    },
+    Rule {
+        pattern: "xpad-*",
+        state: Some(State::Info),
+    },
+    Rule {
+        pattern: "zfs",
+        state: Some(State::Critical),
+    },
+];
+
+fn finding(items: &[&str]) -> Finding {
+    Finding {
+        label: "Unpaired".to_string(),
+        items: items.iter().map(|item| item.to_string()).collect(),
+    }
+}
+
+#[test]
+fn globs_match_the_whole_text() {
+    assert!(glob_match("xpad-*", "xpad-noone"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("a*b*c", "aXXbYYbc"));
+    assert!(glob_match("n?x", "nix"));
+    assert!(!glob_match("xpad-*", "the-xpad-noone"));
+    assert!(!glob_match("zfs", "zfs-kmod"));
+}
+
+#[test]
+fn ignored_items_are_dropped() {
+    assert_eq!(
+        apply(
+            vec![
+                finding(&["v4l2loopback", "xone"]),
+                finding(&["v4l2loopback"])
+            ],
+            RULES
+        ),
+        [finding(&["xone"])]
+    );
+}
+
+#[test]
+fn findings_take_the_worst_state_of_their_items() {
+    assert_eq!(state(&finding(&["xpad-noone"]), RULES), State::Info);
+    assert_eq!(
+        state(&finding(&["xpad-noone", "xone"]), RULES),
+        State::Warning
+    );
+    assert_eq!(
+        state(&finding(&["xpad-noone", "zfs"]), RULES),
+        State::Critical
+    );
+}
+
+#[test]
+fn items_below_the_minimum_severity_are_dropped() {
+    let findings = vec![finding(&["xpad-noone", "zfs"]), finding(&["xpad-one"])];
+
+    assert_eq!(
+        at_least(findings.clone(), RULES, State::Warning),
+        [finding(&["zfs"])]
+    );
+    assert_eq!(at_least(findings.clone(), RULES, State::Info), findings);
+
+    // nothing left to raise a good age
+    let shown = at_least(vec![finding(&["xpad-one"])], RULES, State::Warning);
+    assert!(shown.is_empty());
+    let states = std::iter::once(("age", State::Good)).chain(
+        shown
+            .iter()
+            .map(|finding| ("Unpaired", state(finding, RULES))),
+    );
+    assert_eq!(worst(states), Some(("age", State::Good)));
+}
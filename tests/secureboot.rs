@@ -0,0 +1,82 @@
+use i3status_nix_update_widget::secureboot::{
+    parse_sbctl_verify, pe_is_signed, unsigned_efi_files, unsigned_files,
+};
+
+#[test]
+fn finds_unsigned_files() {
+    let output = "\
+Verifying file database and EFI images in /boot...
+✓ /boot/EFI/BOOT/BOOTX64.EFI is signed
+✓ /boot/EFI/Linux/nixos-generation-41-abc.efi is signed
+✗ /boot/EFI/Linux/nixos-generation-42-def.efi is not signed
+";
+    assert_eq!(
+        parse_sbctl_verify(output),
+        ["/boot/EFI/Linux/nixos-generation-42-def.efi"]
+    );
+}
+
+#[test]
+fn all_signed_is_empty() {
+    assert!(parse_sbctl_verify("✓ /boot/EFI/BOOT/BOOTX64.EFI is signed\n").is_empty());
+}
+
+/// Just enough of a PE32+ image for the header walk, with a certificate table of `cert_size`.
+fn pe_image(cert_size: u32) -> Vec<u8> {
+    let mut image = vec![0u8; 512];
+    image[..2].copy_from_slice(b"MZ");
+    image[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+    image[0x80..0x84].copy_from_slice(b"PE\0\0");
+    let optional = 0x80 + 24;
+    image[optional..optional + 2].copy_from_slice(&0x20Bu16.to_le_bytes());
+    image[optional + 108..optional + 112].copy_from_slice(&16u32.to_le_bytes());
+    let certificate_table = optional + 112 + 4 * 8;
+    image[certificate_table + 4..certificate_table + 8].copy_from_slice(&cert_size.to_le_bytes());
+    image
+}
+
+#[test]
+fn reads_certificate_table_from_pe_headers() {
+    assert_eq!(pe_is_signed(&pe_image(0x1000)), Some(true));
+    assert_eq!(pe_is_signed(&pe_image(0)), Some(false));
+    assert_eq!(pe_is_signed(b"\x7fELF"), None);
+    assert_eq!(pe_is_signed(&pe_image(0x1000)[..0x90]), None);
+}
+
+#[test]
+fn finds_unsigned_efi_files_without_sbctl() {
+    let esp = tempfile::tempdir().unwrap();
+    let linux = esp.path().join("Linux");
+    std::fs::create_dir_all(&linux).unwrap();
+    std::fs::write(linux.join("nixos-generation-41.efi"), pe_image(0x1000)).unwrap();
+    std::fs::write(linux.join("nixos-generation-42.efi"), pe_image(0)).unwrap();
+    std::fs::write(linux.join("README.txt"), "not an EFI binary").unwrap();
+
+    assert_eq!(
+        unsigned_efi_files(esp.path()).unwrap(),
+        [linux
+            .join("nixos-generation-42.efi")
+            .to_string_lossy()
+            .into_owned()]
+    );
+}
+
+#[test]
+fn a_missing_sbctl_falls_back_to_reading_the_esp() {
+    let esp = tempfile::tempdir().unwrap();
+    std::fs::write(esp.path().join("BOOTX64.EFI"), pe_image(0)).unwrap();
+    let unsigned = [esp
+        .path()
+        .join("BOOTX64.EFI")
+        .to_string_lossy()
+        .into_owned()];
+
+    assert_eq!(
+        unsigned_files(Some("/nonexistent/sbctl"), esp.path()).unwrap(),
+        unsigned
+    );
+    assert_eq!(unsigned_files(None, esp.path()).unwrap(), unsigned);
+
+    // one that's there but fails is still an error
+    assert!(unsigned_files(Some("false"), esp.path()).is_err());
+}
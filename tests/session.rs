@@ -0,0 +1,9 @@
+use i3status_nix_update_widget::session::parse_hints;
+
+#[test]
+fn idle_or_locked_sessions_are_idle() {
+    assert!(!parse_hints("IdleHint=no\nLockedHint=no\n"));
+    assert!(parse_hints("IdleHint=yes\nLockedHint=no\n"));
+    assert!(parse_hints("IdleHint=no\nLockedHint=yes\n"));
+    assert!(!parse_hints(""));
+}
@@ -0,0 +1,316 @@
+use chrono::TimeZone;
+use i3status_nix_update_widget::{
+    add_tracked, calendar_days, countdown, error::Error, lock_age, lock_date, parse_timezone,
+    state_for_age, thresholds_at, worst, FixedClock, State, Thresholds, Tracked, Window,
+};
+
+const THRESHOLDS: Thresholds = Thresholds {
+    good: 3,
+    update: 4,
+    out_of_date: 14,
+};
+
+const MODIFIED_DATE: i64 = 1697089731;
+
+fn days_after_modified(days: i64) -> FixedClock {
+    FixedClock(chrono::Utc.timestamp_opt(MODIFIED_DATE, 0).unwrap() + chrono::Duration::days(days))
+}
+
+#[test]
+fn age_counts_whole_days() {
+    assert_eq!(
+        lock_age(&days_after_modified(0), MODIFIED_DATE)
+            .unwrap()
+            .days,
+        0
+    );
+    assert_eq!(
+        lock_age(&days_after_modified(9), MODIFIED_DATE)
+            .unwrap()
+            .days,
+        9
+    );
+
+    let almost_a_day = FixedClock(
+        chrono::Utc.timestamp_opt(MODIFIED_DATE, 0).unwrap() + chrono::Duration::hours(23),
+    );
+    assert_eq!(lock_age(&almost_a_day, MODIFIED_DATE).unwrap().days, 0);
+}
+
+#[test]
+fn age_rejects_out_of_range_timestamps() {
+    assert!(matches!(
+        lock_age(&days_after_modified(0), i64::MAX),
+        Err(Error::FlakeLock(_))
+    ));
+}
+
+#[test]
+fn calendar_days_count_midnights_in_the_timezone() {
+    let tz = parse_timezone("+10:00").unwrap();
+    // 23:00 and 01:00 the next day in +10:00, only two hours apart
+    let locked = tz.with_ymd_and_hms(2024, 5, 2, 23, 0, 0).unwrap();
+    let now = tz.with_ymd_and_hms(2024, 5, 3, 1, 0, 0).unwrap();
+    assert_eq!(calendar_days(&now, &locked), 1);
+
+    // the same instants are on one day in UTC
+    let utc = parse_timezone("UTC").unwrap();
+    assert_eq!(
+        calendar_days(&now.with_timezone(&utc), &locked.with_timezone(&utc)),
+        0
+    );
+
+    assert_eq!(calendar_days(&locked, &now), 0);
+}
+
+#[test]
+fn unknown_timezones_are_config_errors() {
+    assert!(matches!(
+        parse_timezone("Mars/Olympus_Mons"),
+        Err(Error::Config(_))
+    ));
+}
+
+#[test]
+fn lock_date_as_day_or_iso_week() {
+    let age = lock_age(&days_after_modified(3), MODIFIED_DATE).unwrap();
+    assert_eq!(lock_date(&age.locked, false), "locked 2023-10-12");
+    assert_eq!(lock_date(&age.locked, true), "locked 2023-W41");
+
+    // the first days of January can still be in the previous year's last week
+    let new_year = chrono::Utc.with_ymd_and_hms(2021, 1, 2, 12, 0, 0).unwrap();
+    assert_eq!(lock_date(&new_year, true), "locked 2020-W53");
+}
+
+#[test]
+fn state_at_boundaries() {
+    let state = |days| {
+        let age = lock_age(&days_after_modified(days), MODIFIED_DATE).unwrap();
+        state_for_age(age.days, &THRESHOLDS)
+    };
+
+    assert_eq!(state(0), State::Good);
+    assert_eq!(state(3), State::Good);
+    assert_eq!(state(4), State::Warning);
+    assert_eq!(state(13), State::Warning);
+    assert_eq!(state(14), State::Critical);
+    assert_eq!(state(400), State::Critical);
+}
+
+#[test]
+fn state_in_gap_between_good_and_update_is_info() {
+    let gapped = Thresholds {
+        good: 3,
+        update: 7,
+        out_of_date: 14,
+    };
+    assert_eq!(state_for_age(3, &gapped), State::Good);
+    assert_eq!(state_for_age(4, &gapped), State::Info);
+    assert_eq!(state_for_age(6, &gapped), State::Info);
+    assert_eq!(state_for_age(7, &gapped), State::Warning);
+}
+
+#[test]
+fn equal_thresholds_prefer_the_more_severe_state() {
+    let equal = Thresholds {
+        good: 5,
+        update: 5,
+        out_of_date: 5,
+    };
+    assert_eq!(state_for_age(4, &equal), State::Good);
+    assert_eq!(state_for_age(5, &equal), State::Critical);
+
+    let no_warning = Thresholds {
+        good: 3,
+        update: 10,
+        out_of_date: 10,
+    };
+    assert_eq!(state_for_age(10, &no_warning), State::Critical);
+}
+
+#[test]
+fn countdown_to_the_next_threshold() {
+    assert_eq!(countdown(0, &THRESHOLDS), "update within 4d");
+    assert_eq!(countdown(3, &THRESHOLDS), "update within 1d");
+    assert_eq!(countdown(4, &THRESHOLDS), "out of date in 10d");
+    assert_eq!(countdown(13, &THRESHOLDS), "out of date in 1d");
+    assert_eq!(countdown(14, &THRESHOLDS), "out of date");
+}
+
+#[test]
+fn future_timestamps_are_good() {
+    assert_eq!(state_for_age(-2, &THRESHOLDS), State::Good);
+}
+
+#[test]
+fn future_lock_is_clamped_and_flagged_as_skew() {
+    let age = lock_age(&days_after_modified(-2), MODIFIED_DATE).unwrap();
+    assert_eq!(age.days, 0);
+    assert!(age.clock_skew);
+
+    let slightly_ahead = FixedClock(
+        chrono::Utc.timestamp_opt(MODIFIED_DATE, 0).unwrap() - chrono::Duration::minutes(5),
+    );
+    let age = lock_age(&slightly_ahead, MODIFIED_DATE).unwrap();
+    assert_eq!(age.days, 0);
+    assert!(age.clock_skew);
+
+    assert!(
+        !lock_age(&days_after_modified(0), MODIFIED_DATE)
+            .unwrap()
+            .clock_skew
+    );
+}
+
+#[test]
+fn schedule_picks_first_matching_window() {
+    use chrono::Weekday::*;
+
+    let schedule = [
+        Window {
+            weekdays: &[Sat, Sun],
+            hours: 8..20,
+            thresholds: Thresholds {
+                good: 1,
+                update: 2,
+                out_of_date: 5,
+            },
+        },
+        Window {
+            weekdays: &[Sat],
+            hours: 0..24,
+            thresholds: Thresholds {
+                good: 2,
+                update: 3,
+                out_of_date: 6,
+            },
+        },
+    ];
+    let at = |day, hour| {
+        chrono::NaiveDate::from_isoywd_opt(2024, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 30, 0)
+            .unwrap()
+    };
+
+    assert_eq!(
+        thresholds_at(&at(Wed, 12), &schedule, &THRESHOLDS).out_of_date,
+        14
+    );
+    assert_eq!(
+        thresholds_at(&at(Sat, 12), &schedule, &THRESHOLDS).out_of_date,
+        5
+    );
+    assert_eq!(
+        thresholds_at(&at(Sun, 19), &schedule, &THRESHOLDS).out_of_date,
+        5
+    );
+    assert_eq!(
+        thresholds_at(&at(Sun, 20), &schedule, &THRESHOLDS).out_of_date,
+        14
+    );
+    assert_eq!(
+        thresholds_at(&at(Sat, 7), &schedule, &THRESHOLDS).out_of_date,
+        6
+    );
+}
+
+#[test]
+fn state_names_follow_the_configured_style() {
+    use i3status_nix_update_widget::StateNames;
+
+    assert_eq!(State::Critical.name(&StateNames::Variant), "Critical");
+    assert_eq!(State::Critical.name(&StateNames::Lowercase), "critical");
+    assert_eq!(State::Good.name(&StateNames::Numeric), 0);
+    assert_eq!(State::Critical.name(&StateNames::Numeric), 3);
+    let colours = StateNames::Custom {
+        info: "#ffffff",
+        good: "#00ff00",
+        warning: "#ffff00",
+        critical: "#ff0000",
+    };
+    assert_eq!(State::Warning.name(&colours), "#ffff00");
+}
+
+#[test]
+fn worst_state_wins_and_says_where_from() {
+    use i3status_nix_update_widget::worst;
+
+    assert!(State::Good < State::Info);
+    assert!(State::Warning < State::Critical);
+    assert_eq!(
+        worst([
+            ("age", State::Info),
+            ("Reboot", State::Warning),
+            ("Restart", State::Warning),
+        ]),
+        Some(("Reboot", State::Warning))
+    );
+    assert_eq!(
+        worst([("age", State::Critical), ("Reboot", State::Warning)]),
+        Some(("age", State::Critical))
+    );
+    assert_eq!(worst([]), None);
+}
+
+#[test]
+fn tracked_flakes_are_aged_by_their_own_thresholds() {
+    let tracked = |name, days: i64| Tracked {
+        name,
+        modified_date: MODIFIED_DATE - days * 86400,
+        thresholds: Thresholds {
+            good: 30,
+            update: 60,
+            out_of_date: 90,
+        },
+    };
+    // the main lock is 5 days old, a warning by its thresholds
+    let clock = days_after_modified(5);
+    let block = |tracked: &[Tracked]| {
+        let mut text = "Age: 5".to_string();
+        let mut states = vec![("age".to_string(), state_for_age(5, &THRESHOLDS))];
+        add_tracked(
+            tracked,
+            |tracked| lock_age(&clock, tracked.modified_date).map(|age| age.days),
+            &mut text,
+            &mut states,
+        )
+        .unwrap();
+        let (cause, state) = worst(states.iter().map(|(cause, state)| (cause.as_str(), *state)))
+            .map(|(cause, state)| (cause.to_string(), state))
+            .unwrap();
+        (text, cause, state)
+    };
+
+    // older than the main lock, but still good for a flake that's meant to move slower
+    assert_eq!(
+        block(&[tracked("dotfiles", 20)]),
+        (
+            "Age: 5 dotfiles: 25".to_string(),
+            "age".to_string(),
+            State::Warning
+        )
+    );
+    // old enough by its own thresholds to be the worst
+    assert_eq!(
+        block(&[tracked("dotfiles", 20), tracked("work", 90)]),
+        (
+            "Age: 5 dotfiles: 25 work: 95".to_string(),
+            "work".to_string(),
+            State::Critical
+        )
+    );
+    // newer than the main lock
+    let newer = Tracked {
+        modified_date: MODIFIED_DATE + 3 * 86400,
+        ..tracked("dotfiles", 0)
+    };
+    assert_eq!(
+        block(&[newer]),
+        (
+            "Age: 5 dotfiles: 2".to_string(),
+            "age".to_string(),
+            State::Warning
+        )
+    );
+}
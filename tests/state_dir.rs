@@ -0,0 +1,102 @@
+use i3status_nix_update_widget::state_dir::{write_atomically, StateDir, Stored};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+struct Counter {
+    count: u32,
+}
+
+impl Stored for Counter {
+    const NAME: &'static str = "counter.json";
+    const VERSION: u32 = 2;
+}
+
+#[test]
+fn writes_atomically() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nix_update.prom");
+
+    write_atomically(&path, "old\n").unwrap();
+    write_atomically(&path, "new\n").unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "new\n");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn round_trips_and_creates_the_directory() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = StateDir::at(root.path().join("nested/state"));
+
+    assert_eq!(dir.read::<Counter>(), None);
+    dir.write(&Counter { count: 3 }).unwrap();
+    assert_eq!(dir.read::<Counter>(), Some(Counter { count: 3 }));
+    assert_eq!(
+        std::fs::read_to_string(dir.path.join("counter.json")).unwrap(),
+        r#"{"version":2,"data":{"count":3}}"#
+    );
+}
+
+#[test]
+fn other_versions_and_garbage_are_ignored() {
+    let root = tempfile::tempdir().unwrap();
+    let dir = StateDir::at(root.path());
+
+    std::fs::write(
+        root.path().join("counter.json"),
+        r#"{"version":1,"data":{"count":3}}"#,
+    )
+    .unwrap();
+    assert_eq!(dir.read::<Counter>(), None);
+
+    std::fs::write(root.path().join("counter.json"), "{not json").unwrap();
+    assert_eq!(dir.read::<Counter>(), None);
+}
+
+#[test]
+fn user_dir_follows_xdg() {
+    // the only test touching these variables, so there's no one to race with
+    std::env::set_var("XDG_STATE_HOME", "/tmp/xdg-state");
+    assert_eq!(
+        StateDir::user().unwrap().path,
+        std::path::Path::new("/tmp/xdg-state/i3status-nix-update-widget")
+    );
+
+    std::env::set_var("XDG_STATE_HOME", "");
+    std::env::set_var("HOME", "/home/someone");
+    assert_eq!(
+        StateDir::user().unwrap().path,
+        std::path::Path::new("/home/someone/.local/state/i3status-nix-update-widget")
+    );
+}
+
+#[test]
+fn read_only_dirs_are_read_but_never_written() {
+    let root = tempfile::tempdir().unwrap();
+    StateDir::at(root.path())
+        .write(&Counter { count: 3 })
+        .unwrap();
+    std::fs::remove_file(root.path().join(".lock")).unwrap();
+
+    let dir = StateDir::at(root.path()).read_only();
+    assert_eq!(dir.read::<Counter>(), Some(Counter { count: 3 }));
+    assert!(dir.write(&Counter { count: 4 }).is_err());
+    assert_eq!(dir.read::<Counter>(), Some(Counter { count: 3 }));
+    // not even a lock file
+    assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn configured_dirs_win_over_the_defaults() {
+    let root = tempfile::tempdir().unwrap();
+    let persist = root.path().join("persist/state");
+    let configured = persist.to_str();
+
+    assert_eq!(StateDir::system_or(configured), StateDir::at(&persist));
+    assert_eq!(StateDir::user_or(configured), Some(StateDir::at(&persist)));
+    assert_eq!(StateDir::system_or(None), StateDir::system());
+
+    StateDir::system_or(configured)
+        .write(&Counter { count: 3 })
+        .unwrap();
+    assert!(persist.join("counter.json").exists());
+}
@@ -0,0 +1,20 @@
+use i3status_nix_update_widget::theme::{self, select};
+use i3status_nix_update_widget::{error::Error, State};
+
+#[test]
+fn selects_named_themes() {
+    assert_eq!(select("nord", false).unwrap(), &theme::NORD);
+    assert_eq!(
+        select("gruvbox", false).unwrap().color(State::Critical),
+        Some("#FB4934")
+    );
+    assert!(matches!(select("vaporwave", false), Err(Error::Config(_))));
+}
+
+#[test]
+fn no_color_wins() {
+    let theme = select("catppuccin", true).unwrap();
+    for state in [State::Info, State::Good, State::Warning, State::Critical] {
+        assert_eq!(theme.color(state), None);
+    }
+}
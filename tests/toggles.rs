@@ -0,0 +1,62 @@
+use i3status_nix_update_widget::state_dir::StateDir;
+use i3status_nix_update_widget::toggles::Disabled;
+
+#[test]
+fn checks_stay_off_until_enabled_or_their_time_is_up() {
+    let mut disabled = Disabled::default();
+    disabled.disable("failed", None);
+    disabled.disable("registry", Some(1000));
+
+    assert!(disabled.is_off("failed", 5000));
+    assert!(disabled.is_off("registry", 999));
+    assert!(!disabled.is_off("registry", 1000));
+    assert!(!disabled.is_off("reboot", 0));
+    assert_eq!(disabled.off(999), ["failed", "registry"]);
+    assert_eq!(disabled.off(1000), ["failed"]);
+
+    disabled.enable("failed");
+    assert!(!disabled.is_off("failed", 5000));
+}
+
+#[test]
+fn disabling_again_replaces_the_expiry() {
+    let mut disabled = Disabled::default();
+    disabled.disable("units", Some(100));
+    disabled.disable("units", None);
+
+    assert_eq!(disabled.checks, [("units".to_string(), None)]);
+    assert!(disabled.is_off("units", 200));
+}
+
+#[test]
+fn disabled_checks_are_kept_in_the_state_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_dir = StateDir::at(dir.path());
+    let mut disabled = Disabled::default();
+    disabled.disable("etc-drift", Some(42));
+    state_dir.write(&disabled).unwrap();
+
+    assert_eq!(state_dir.read::<Disabled>(), Some(disabled));
+}
+
+#[test]
+fn loading_doesnt_wait_on_the_lock() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut disabled = Disabled::default();
+    disabled.disable("failed", None);
+    StateDir::at(dir.path()).write(&disabled).unwrap();
+
+    // a `disable` from another process halfway through its write
+    let lock = std::fs::File::open(dir.path().join(".lock")).unwrap();
+    lock.lock().unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let path = dir.path().to_owned();
+    std::thread::spawn(move || sender.send(Disabled::load(Some(StateDir::at(path)))));
+    let loaded = receiver
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("blocked on the lock");
+    assert_eq!(loaded, disabled);
+
+    assert_eq!(Disabled::load(None), Disabled::default());
+}
@@ -0,0 +1,14 @@
+use i3status_nix_update_widget::parse_uptime;
+
+#[test]
+fn parses_proc_uptime() {
+    let uptime = parse_uptime("3456789.12 12345678.90\n").unwrap();
+    assert_eq!(uptime.num_seconds(), 3456789);
+    assert_eq!(uptime.num_days(), 40);
+}
+
+#[test]
+fn rejects_garbage() {
+    assert!(parse_uptime("").is_err());
+    assert!(parse_uptime("soon 1.0").is_err());
+}
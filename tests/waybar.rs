@@ -0,0 +1,32 @@
+use i3status_nix_update_widget::waybar::{percentage, Block};
+use i3status_nix_update_widget::{BarCommand, Finding, State};
+
+#[test]
+fn block_has_class_tooltip_and_percentage() {
+    let command = BarCommand {
+        icon: "cogs".to_string(),
+        state: State::Warning,
+        text: "Age: 7 Restart: 2".to_string(),
+    };
+    let findings = [Finding {
+        label: "Restart".to_string(),
+        items: vec!["firefox".to_string(), "sshd".to_string()],
+    }];
+    assert_eq!(
+        serde_json::to_string(&Block::new(&command, &findings, None, 50)).unwrap(),
+        r#"{"text":"Age: 7 Restart: 2","tooltip":"Restart: firefox\nRestart: sshd","class":"warning","percentage":50}"#
+    );
+
+    let updated = Block::new(&command, &[], Some("updated today: nixpkgs +9d"), 50);
+    assert_eq!(updated.tooltip, "updated today: nixpkgs +9d");
+}
+
+#[test]
+fn percentage_of_the_out_of_date_threshold() {
+    assert_eq!(percentage(0, 14), 0);
+    assert_eq!(percentage(7, 14), 50);
+    assert_eq!(percentage(13, 14), 92);
+    assert_eq!(percentage(30, 14), 100);
+    assert_eq!(percentage(-1, 14), 0);
+    assert_eq!(percentage(3, 0), 100);
+}
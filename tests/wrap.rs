@@ -0,0 +1,43 @@
+use i3status_nix_update_widget::wrap::inject;
+
+fn ours() -> serde_json::Value {
+    serde_json::json!({ "full_text": "Age: 9", "name": "nix-update" })
+}
+
+#[test]
+fn header_and_opening_bracket_pass_through() {
+    assert_eq!(
+        inject(r#"{"version":1,"click_events":true}"#, &ours(), 0),
+        None
+    );
+    assert_eq!(inject("[", &ours(), 0), None);
+}
+
+#[test]
+fn injects_into_first_status_line() {
+    assert_eq!(
+        inject(r#"[{"full_text":"W: down"}]"#, &ours(), 0).unwrap(),
+        r#"[{"full_text":"Age: 9","name":"nix-update"},{"full_text":"W: down"}]"#
+    );
+}
+
+#[test]
+fn keeps_leading_comma_of_later_lines() {
+    assert_eq!(
+        inject(
+            r#",[{"full_text":"W: down"},{"full_text":"12:00"}]"#,
+            &ours(),
+            1
+        )
+        .unwrap(),
+        r#",[{"full_text":"W: down"},{"full_text":"Age: 9","name":"nix-update"},{"full_text":"12:00"}]"#
+    );
+}
+
+#[test]
+fn position_past_the_end_appends() {
+    assert_eq!(
+        inject(r#",[{"full_text":"12:00"}]"#, &ours(), 10).unwrap(),
+        r#",[{"full_text":"12:00"},{"full_text":"Age: 9","name":"nix-update"}]"#
+    );
+}